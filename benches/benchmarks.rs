@@ -6,7 +6,7 @@ extern crate rand;
 use base64::display;
 use base64::{
     decode, decode_config_buf, decode_config_slice, encode, encode_config_buf, encode_config_slice,
-    write, Config,
+    write, CharacterSet, Config, CustomAlphabet,
 };
 
 use criterion::{black_box, Bencher, Criterion, Throughput, BenchmarkId};
@@ -44,8 +44,7 @@ fn do_decode_bench_slice(b: &mut Bencher, &size: &usize) {
     fill(&mut v);
     let encoded = encode(&v);
 
-    let mut buf = Vec::new();
-    buf.resize(size, 0);
+    let mut buf = vec![0; size];
     b.iter(|| {
         decode_config_slice(&encoded, TEST_CONFIG, &mut buf).unwrap();
         black_box(&buf);
@@ -57,8 +56,7 @@ fn do_decode_bench_stream(b: &mut Bencher, &size: &usize) {
     fill(&mut v);
     let encoded = encode(&v);
 
-    let mut buf = Vec::new();
-    buf.resize(size, 0);
+    let mut buf = vec![0; size];
     buf.truncate(0);
 
     b.iter(|| {
@@ -101,9 +99,8 @@ fn do_encode_bench_reuse_buf(b: &mut Bencher, &size: &usize) {
 fn do_encode_bench_slice(b: &mut Bencher, &size: &usize) {
     let mut v: Vec<u8> = Vec::with_capacity(size);
     fill(&mut v);
-    let mut buf = Vec::new();
     // conservative estimate of encoded size
-    buf.resize(v.len() * 2, 0);
+    let mut buf = vec![0; v.len() * 2];
     b.iter(|| {
         encode_config_slice(&v, TEST_CONFIG, &mut buf);
     });
@@ -112,9 +109,7 @@ fn do_encode_bench_slice(b: &mut Bencher, &size: &usize) {
 fn do_encode_bench_stream(b: &mut Bencher, &size: &usize) {
     let mut v: Vec<u8> = Vec::with_capacity(size);
     fill(&mut v);
-    let mut buf = Vec::new();
-
-    buf.reserve(size * 2);
+    let mut buf = Vec::with_capacity(size * 2);
     b.iter(|| {
         buf.clear();
         let mut stream_enc = write::EncoderWriter::new(&mut buf, TEST_CONFIG);
@@ -149,6 +144,29 @@ fn do_encode_bench_string_reuse_buf_stream(b: &mut Bencher, &size: &usize) {
     });
 }
 
+fn do_encode_bench_reuse_buf_config(b: &mut Bencher, &(size, config): &(usize, Config)) {
+    let mut v: Vec<u8> = Vec::with_capacity(size);
+    fill(&mut v);
+    let mut buf = String::new();
+    b.iter(|| {
+        encode_config_buf(&v, config, &mut buf);
+        buf.clear();
+    });
+}
+
+fn do_decode_bench_reuse_buf_config(b: &mut Bencher, &(size, config): &(usize, Config)) {
+    let mut v: Vec<u8> = Vec::with_capacity(size * 3 / 4);
+    fill(&mut v);
+    let encoded = base64::encode_config(&v, config);
+
+    let mut buf = Vec::new();
+    b.iter(|| {
+        decode_config_buf(&encoded, config, &mut buf).unwrap();
+        black_box(&buf);
+        buf.clear();
+    });
+}
+
 fn fill(v: &mut Vec<u8>) {
     let cap = v.capacity();
     // weak randomness is plenty; we just want to not be completely friendly to the branch predictor
@@ -158,7 +176,8 @@ fn fill(v: &mut Vec<u8>) {
     }
 }
 
-const BYTE_SIZES: [usize; 5] = [3, 50, 100, 500, 3 * 1024];
+// 24 is the length of an encoded 16 or 18 byte key, most of which is decoded after the fast loop
+const BYTE_SIZES: [usize; 6] = [3, 24, 50, 100, 500, 3 * 1024];
 
 // Benchmarks over these byte sizes take longer so we will run fewer samples to
 // keep the benchmark runtime reasonable.
@@ -206,11 +225,40 @@ fn decode_benchmarks(c: &mut Criterion, label: &str, byte_sizes: &[usize]) {
     group.finish();
 }
 
+// Custom alphabets build their decode table on each call, which matters most for small inputs
+fn charset_benchmarks(c: &mut Criterion, label: &str, byte_sizes: &[usize]) {
+    let custom = CustomAlphabet::new(b"zyxwvutsrqponmlkjihgfedcbaZYXWVUTSRQPONMLKJIHGFEDCBA9876543210-_")
+        .unwrap();
+    let configs = [
+        ("standard", base64::STANDARD),
+        ("url_safe", base64::URL_SAFE),
+        ("custom", Config::with_charset(CharacterSet::Custom(custom))),
+    ];
+
+    let mut group = c.benchmark_group(label);
+    group
+        .warm_up_time(std::time::Duration::from_millis(500))
+        .measurement_time(std::time::Duration::from_secs(3));
+
+    for size in byte_sizes {
+        group.throughput(Throughput::Bytes(*size as u64));
+
+        for &(name, config) in configs.iter() {
+            group
+                .bench_with_input(BenchmarkId::new(format!("encode_reuse_buf_{}", name), size), &(*size, config), do_encode_bench_reuse_buf_config)
+                .bench_with_input(BenchmarkId::new(format!("decode_reuse_buf_{}", name), size), &(*size, config), do_decode_bench_reuse_buf_config);
+        }
+    }
+
+    group.finish();
+}
+
 fn bench(c: &mut Criterion) {
     encode_benchmarks(c, "encode_small_input", &BYTE_SIZES[..]);
     encode_benchmarks(c, "encode_large_input", &LARGE_BYTE_SIZES[..]);
     decode_benchmarks(c, "decode_small_input", &BYTE_SIZES[..]);
     decode_benchmarks(c, "decode_large_input", &LARGE_BYTE_SIZES[..]);
+    charset_benchmarks(c, "charsets", &BYTE_SIZES[..]);
 }
 
 criterion_group!(benches, bench);
@@ -2,43 +2,10 @@ use std::fs::File;
 use std::io::{self, Read};
 use std::path::PathBuf;
 use std::process;
-use std::str::FromStr;
 
-use base64::{read, write};
+use base64::{read, write, CharacterSet, Config};
 use structopt::StructOpt;
 
-#[derive(Debug, StructOpt)]
-enum CharacterSet {
-    Standard,
-    UrlSafe,
-}
-
-impl Default for CharacterSet {
-    fn default() -> Self {
-        CharacterSet::Standard
-    }
-}
-
-impl Into<base64::Config> for CharacterSet {
-    fn into(self) -> base64::Config {
-        match self {
-            CharacterSet::Standard => base64::STANDARD,
-            CharacterSet::UrlSafe => base64::URL_SAFE,
-        }
-    }
-}
-
-impl FromStr for CharacterSet {
-    type Err = String;
-    fn from_str(s: &str) -> Result<CharacterSet, String> {
-        match s {
-            "standard" => Ok(CharacterSet::Standard),
-            "urlsafe" => Ok(CharacterSet::UrlSafe),
-            _ => Err(format!("charset '{}' unrecognized", s)),
-        }
-    }
-}
-
 /// Base64 encode or decode FILE (or standard input), to standard output.
 #[derive(Debug, StructOpt)]
 struct Opt {
@@ -46,7 +13,8 @@ struct Opt {
     #[structopt(short = "d", long = "decode")]
     decode: bool,
     /// The character set to choose. Defaults to the standard base64 character set.
-    /// Supported character sets include "standard" and "urlsafe".
+    /// Supported character sets include "standard", "url-safe", "crypt", "bcrypt", "imap-mutf7",
+    /// and "binhex", or a custom alphabet can be given as its 64 symbols.
     #[structopt(long = "charset")]
     charset: Option<CharacterSet>,
     /// The file to encode/decode.
@@ -68,7 +36,7 @@ fn main() {
         }
         Some(f) => Box::new(File::open(f).unwrap()),
     };
-    let config = opt.charset.unwrap_or_default().into();
+    let config = Config::with_charset(opt.charset.unwrap_or(CharacterSet::Standard));
     let stdout = io::stdout();
     let mut stdout = stdout.lock();
     let r = if opt.decode {
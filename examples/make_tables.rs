@@ -2,6 +2,19 @@ use std::collections::{HashMap, HashSet};
 use std::iter::Iterator;
 
 fn main() {
+    println!("//! Encode and decode tables for the built-in character sets.");
+    println!("//!");
+    println!("//! An encode table is a `[u8; 64]` holding the symbol for each 6-bit value, in order.");
+    println!("//! A decode table is a `[u8; 256]` indexed by input byte, holding the 6-bit value (0 to");
+    println!("//! 63) of each symbol and `INVALID_VALUE` for every other byte. `CustomAlphabet` builds");
+    println!("//! its tables the same way at runtime.");
+    println!("//!");
+    println!("//! Generated by `examples/make_tables.rs`.");
+    println!();
+    println!("/// The value in a decode table for bytes that aren't symbols.");
+    println!("///");
+    println!("/// Symbols decode to at most 63, so checking the top two bits of a decoded value is enough");
+    println!("/// to tell whether it's invalid.");
     println!("pub const INVALID_VALUE: u8 = 255;");
 
     // A-Z
@@ -15,8 +28,8 @@ fn main() {
         // /
         .chain(0x2F..0x30)
         .collect();
-    print_encode_table(&standard_alphabet, "STANDARD_ENCODE", 0);
-    print_decode_table(&standard_alphabet, "STANDARD_DECODE", 0);
+    print_encode_table(&standard_alphabet, "STANDARD_ENCODE", "standard", 0);
+    print_decode_table(&standard_alphabet, "STANDARD_DECODE", "standard", 0);
 
     // A-Z
     let url_alphabet: Vec<u8> = (0x41..0x5B)
@@ -29,8 +42,16 @@ fn main() {
         // _
         .chain(0x5F..0x60)
         .collect();
-    print_encode_table(&url_alphabet, "URL_SAFE_ENCODE", 0);
-    print_decode_table(&url_alphabet, "URL_SAFE_DECODE", 0);
+    print_encode_table(&url_alphabet, "URL_SAFE_ENCODE", "URL-safe", 0);
+    print_decode_table(&url_alphabet, "URL_SAFE_DECODE", "URL-safe", 0);
+
+    // both of the above, with their differing symbols for 62 and 63 as aliases
+    print_merged_decode_table(
+        &[&standard_alphabet, &url_alphabet],
+        "STANDARD_OR_URL_SAFE_DECODE",
+        "standard-or-URL-safe",
+        0,
+    );
 
     // ./0123456789
     let crypt_alphabet: Vec<u8> = (b'.'..(b'9' + 1))
@@ -39,8 +60,8 @@ fn main() {
         // a-z
         .chain(b'a'..(b'z' + 1))
         .collect();
-    print_encode_table(&crypt_alphabet, "CRYPT_ENCODE", 0);
-    print_decode_table(&crypt_alphabet, "CRYPT_DECODE", 0);
+    print_encode_table(&crypt_alphabet, "CRYPT_ENCODE", "crypt", 0);
+    print_decode_table(&crypt_alphabet, "CRYPT_DECODE", "crypt", 0);
 
     // ./
     let bcrypt_alphabet: Vec<u8> = (b'.'..(b'/' + 1))
@@ -51,8 +72,8 @@ fn main() {
         // 0-9
         .chain(b'0'..(b'9' + 1))
         .collect();
-    print_encode_table(&bcrypt_alphabet, "BCRYPT_ENCODE", 0);
-    print_decode_table(&bcrypt_alphabet, "BCRYPT_DECODE", 0);
+    print_encode_table(&bcrypt_alphabet, "BCRYPT_ENCODE", "bcrypt", 0);
+    print_decode_table(&bcrypt_alphabet, "BCRYPT_DECODE", "bcrypt", 0);
 
     // A-Z
     let imap_alphabet: Vec<u8> = (0x41..0x5B)
@@ -65,8 +86,18 @@ fn main() {
         // ,
         .chain(0x2C..0x2D)
         .collect();
-    print_encode_table(&imap_alphabet, "IMAP_MUTF7_ENCODE", 0);
-    print_decode_table(&imap_alphabet, "IMAP_MUTF7_DECODE", 0);
+    print_encode_table(
+        &imap_alphabet,
+        "IMAP_MUTF7_ENCODE",
+        "IMAP-modified UTF-7",
+        0,
+    );
+    print_decode_table(
+        &imap_alphabet,
+        "IMAP_MUTF7_DECODE",
+        "IMAP-modified UTF-7",
+        0,
+    );
 
     // '!' - '-'
     let binhex_alphabet: Vec<u8> = (0x21..0x2E)
@@ -85,12 +116,18 @@ fn main() {
         // p-r
         .chain(0x70..0x73)
         .collect();
-    print_encode_table(&binhex_alphabet, "BINHEX_ENCODE", 0);
-    print_decode_table(&binhex_alphabet, "BINHEX_DECODE", 0);
+    print_encode_table(&binhex_alphabet, "BINHEX_ENCODE", "BinHex", 0);
+    print_decode_table(&binhex_alphabet, "BINHEX_DECODE", "BinHex", 0);
 }
 
-fn print_encode_table(alphabet: &[u8], const_name: &str, indent_depth: usize) {
+fn print_encode_table(alphabet: &[u8], const_name: &str, name: &str, indent_depth: usize) {
     check_alphabet(alphabet);
+    println!(
+        "{:width$}/// Symbols of the {} character set, indexed by their 6-bit value.",
+        "",
+        name,
+        width = indent_depth
+    );
     println!("#[rustfmt::skip]");
     println!(
         "{:width$}pub const {}: &[u8; 64] = &[",
@@ -106,7 +143,7 @@ fn print_encode_table(alphabet: &[u8], const_name: &str, indent_depth: usize) {
             b,
             i,
             i,
-            String::from_utf8(vec![*b as u8]).unwrap(),
+            String::from_utf8(vec![*b]).unwrap(),
             b,
             width = indent_depth + 4
         );
@@ -115,17 +152,44 @@ fn print_encode_table(alphabet: &[u8], const_name: &str, indent_depth: usize) {
     println!("{:width$}];", "", width = indent_depth);
 }
 
-fn print_decode_table(alphabet: &[u8], const_name: &str, indent_depth: usize) {
-    check_alphabet(alphabet);
+fn print_decode_table(alphabet: &[u8], const_name: &str, name: &str, indent_depth: usize) {
+    print_merged_decode_table(&[alphabet], const_name, name, indent_depth);
+}
+
+/// Print a decode table that accepts the symbols of every alphabet in `alphabets`, which must agree
+/// on the value of any symbol they share.
+fn print_merged_decode_table(
+    alphabets: &[&[u8]],
+    const_name: &str,
+    name: &str,
+    indent_depth: usize,
+) {
     // map of alphabet bytes to 6-bit morsels
     let mut input_to_morsel = HashMap::<u8, u8>::new();
 
-    // standard base64 alphabet bytes, in order
-    for (morsel, ascii_byte) in alphabet.iter().enumerate() {
-        // truncation cast is fine here
-        let _ = input_to_morsel.insert(*ascii_byte, morsel as u8);
+    for alphabet in alphabets {
+        check_alphabet(alphabet);
+
+        // base64 alphabet bytes, in order
+        for (morsel, ascii_byte) in alphabet.iter().enumerate() {
+            // truncation cast is fine here
+            if let Some(existing) = input_to_morsel.insert(*ascii_byte, morsel as u8) {
+                assert_eq!(existing, morsel as u8);
+            }
+        }
     }
 
+    println!(
+        "{:width$}/// 6-bit values of the {} character set's symbols, indexed by byte, or",
+        "",
+        name,
+        width = indent_depth
+    );
+    println!(
+        "{:width$}/// `INVALID_VALUE` for bytes that aren't symbols.",
+        "",
+        width = indent_depth
+    );
     println!("#[rustfmt::skip]");
     println!(
         "{:width$}pub const {}: &[u8; 256] = &[",
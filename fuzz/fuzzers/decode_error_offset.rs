@@ -0,0 +1,30 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate base64;
+
+use base64::*;
+
+mod utils;
+
+/// Errors that point at a byte must point at the byte they name, within the input.
+fn check_offset(input: &[u8], result: Result<(), DecodeError>) {
+    match result {
+        Err(DecodeError::InvalidByte(index, byte))
+        | Err(DecodeError::InvalidLastSymbol(index, byte)) => {
+            assert!(index < input.len());
+            assert_eq!(byte, input[index]);
+        }
+        _ => {}
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let config = utils::random_config(data);
+
+    for &config in &[config, config.decode_ignore_invalid(true)] {
+        check_offset(data, decode_config(&data, config).map(|_| ()));
+
+        let mut decoded = vec![0; max_decoded_len(data.len())];
+        check_offset(data, decode_config_slice(&data, config, &mut decoded).map(|_| ()));
+    }
+});
@@ -12,4 +12,8 @@ fuzz_target!(|data: &[u8]| {
     let encoded = encode_config(&data, config);
     let decoded = decode_config(&encoded, config).unwrap();
     assert_eq!(data, decoded.as_slice());
+
+    let mut decoded_slice = vec![0; max_decoded_len(encoded.len())];
+    let decoded_len = decode_config_slice(&encoded, config, &mut decoded_slice).unwrap();
+    assert_eq!(data, &decoded_slice[..decoded_len]);
 });
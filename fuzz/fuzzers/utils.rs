@@ -4,7 +4,7 @@ extern crate rand_pcg;
 extern crate ring;
 
 use self::base64::*;
-use self::rand::{Rng, SeedableRng};
+use self::rand::{seq::SliceRandom, Rng, SeedableRng};
 use self::rand_pcg::Pcg32;
 use self::ring::digest;
 
@@ -17,11 +17,53 @@ pub fn random_config(data: &[u8]) -> Config {
 
     let mut rng = Pcg32::from_seed(seed);
 
-    let charset = if rng.gen() {
-        CharacterSet::UrlSafe
+    const CHARSETS: &[CharacterSet] = &[
+        CharacterSet::Standard,
+        CharacterSet::UrlSafe,
+        CharacterSet::Crypt,
+        CharacterSet::Bcrypt,
+        CharacterSet::ImapMutf7,
+        CharacterSet::BinHex,
+    ];
+
+    let charset = if rng.gen_bool(0.1) {
+        // printable ASCII, minus the default padding byte
+        let mut candidates: Vec<u8> = (b'!'..=b'~').filter(|&b| b != b'=').collect();
+        candidates.shuffle(&mut rng);
+
+        let mut symbols = [0_u8; 64];
+        symbols.copy_from_slice(&candidates[..64]);
+
+        CharacterSet::Custom(CustomAlphabet::new(&symbols).unwrap())
     } else {
-        CharacterSet::Standard
+        *CHARSETS.choose(&mut rng).unwrap()
     };
 
-    Config::new(charset, rng.gen())
+    let config = Config::new(charset, rng.gen()).decode_allow_trailing_bits(rng.gen());
+
+    if rng.gen_bool(0.1) {
+        // any printable ASCII that isn't part of the alphabet will do
+        let symbols = alphabet_symbols(config);
+        let pad_byte = loop {
+            let b = rng.gen_range(b'!', b'~' + 1);
+            if !symbols.contains(&b) {
+                break b;
+            }
+        };
+
+        config.pad_byte(pad_byte)
+    } else {
+        config
+    }
+}
+
+/// The 64 symbols of `config`'s alphabet, found by encoding the 6-bit values 0 to 63.
+fn alphabet_symbols(config: Config) -> Vec<u8> {
+    let mut packed = Vec::new();
+    for value in (0..64_u32).step_by(4) {
+        let quad = value << 18 | (value + 1) << 12 | (value + 2) << 6 | (value + 3);
+        packed.extend_from_slice(&[(quad >> 16) as u8, (quad >> 8) as u8, quad as u8]);
+    }
+
+    encode_config(&packed, config.pad(false)).into_bytes()
 }
@@ -0,0 +1,336 @@
+use crate::{tables, PAD_BYTE};
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    str,
+};
+#[cfg(any(feature = "std", test))]
+use std::error;
+
+/// A base64 alphabet supplied at runtime, for use with `CharacterSet::Custom`.
+///
+/// Only the 64 symbols and their aliases are stored, since `Config` is copied around a lot and a
+/// full decode table would make it several times larger. The decode table is instead built each
+/// time a custom alphabet is used for decoding, which is a small fixed cost per call.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     // the standard alphabet, but with `.` and `_` instead of `+` and `/`
+///     let alphabet = base64::CustomAlphabet::new(
+///         b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789._",
+///     )
+///     .unwrap();
+///     let config =
+///         base64::Config::with_charset(base64::CharacterSet::Custom(alphabet)).pad(false);
+///
+///     assert_eq!("_w", base64::encode_config(&[0xFF], config));
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct CustomAlphabet {
+    symbols: [u8; 64],
+    // a second symbol that decodes to the same value as each symbol, or the symbol itself if none
+    aliases: [u8; 64],
+}
+
+impl CustomAlphabet {
+    /// Create a `CustomAlphabet` from the 64 symbols to use, in order: the symbol at index `i`
+    /// encodes the 6-bit value `i`.
+    ///
+    /// The symbols must all be distinct ASCII bytes (so that encoded output is always valid
+    /// UTF-8), and `=` is not allowed since it is used for padding.
+    pub fn new(symbols: &[u8; 64]) -> Result<CustomAlphabet, AlphabetError> {
+        CustomAlphabet::with_aliases(symbols, symbols)
+    }
+
+    /// Create a `CustomAlphabet` like `new`, where `aliases[i]` is also accepted when decoding as
+    /// the value `i`. Encoding only ever uses `symbols`.
+    ///
+    /// An alias that's the same as its symbol means that symbol has no alias. Other aliases follow
+    /// the same rules as symbols: they must be ASCII, not `=`, and distinct from each other and
+    /// from every symbol.
+    ///
+    /// Aliases aren't part of the alphabet's name, so they're lost if the `CharacterSet` is
+    /// displayed and parsed back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate base64;
+    ///
+    /// fn main() {
+    ///     // an alphabet without lowercase letters, which decodes them as uppercase
+    ///     let symbols = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$%&'()*+,-./:;<>?@[]^_{|}~";
+    ///     let mut lowercase = *symbols;
+    ///     lowercase.make_ascii_lowercase();
+    ///
+    ///     let alphabet = base64::CustomAlphabet::with_aliases(symbols, &lowercase).unwrap();
+    ///     let config = base64::Config::with_charset(base64::CharacterSet::Custom(alphabet));
+    ///
+    ///     let decoded = base64::decode_config("hello+ab", config).unwrap();
+    ///     assert_eq!(base64::decode_config("HELLO+AB", config).unwrap(), decoded);
+    ///     assert_eq!("HELLO+AB", base64::encode_config(&decoded, config));
+    /// }
+    /// ```
+    pub fn with_aliases(
+        symbols: &[u8; 64],
+        aliases: &[u8; 64],
+    ) -> Result<CustomAlphabet, AlphabetError> {
+        let mut decode = [tables::INVALID_VALUE; 256];
+        let mut add = |symbol: u8, value: usize| {
+            if !symbol.is_ascii() {
+                return Err(AlphabetError::NonAsciiByte(symbol));
+            }
+
+            if symbol == PAD_BYTE {
+                return Err(AlphabetError::ReservedByte(symbol));
+            }
+
+            if decode[symbol as usize] != tables::INVALID_VALUE {
+                return Err(AlphabetError::DuplicatedByte(symbol));
+            }
+
+            decode[symbol as usize] = value as u8;
+            Ok(())
+        };
+
+        for (value, &symbol) in symbols.iter().enumerate() {
+            add(symbol, value)?;
+        }
+
+        for (value, (&alias, &symbol)) in aliases.iter().zip(symbols.iter()).enumerate() {
+            if alias != symbol {
+                add(alias, value)?;
+            }
+        }
+
+        Ok(CustomAlphabet {
+            symbols: *symbols,
+            aliases: *aliases,
+        })
+    }
+
+    pub(crate) fn encode_table(&self) -> &[u8; 64] {
+        &self.symbols
+    }
+
+    /// The aliases, if there are any. Each decodes to the same value as the symbol at the same
+    /// index in `encode_table`.
+    pub(crate) fn alias_table(&self) -> Option<&[u8; 64]> {
+        if self.aliases[..] == self.symbols[..] {
+            None
+        } else {
+            Some(&self.aliases)
+        }
+    }
+
+    pub(crate) fn decode_table(&self) -> [u8; 256] {
+        let mut decode = [tables::INVALID_VALUE; 256];
+
+        for (value, (&symbol, &alias)) in self.symbols.iter().zip(self.aliases.iter()).enumerate() {
+            decode[symbol as usize] = value as u8;
+            decode[alias as usize] = value as u8;
+        }
+
+        decode
+    }
+}
+
+// Arrays of 64 bytes don't implement these on the oldest supported Rust, so they can't be derived
+
+impl PartialEq for CustomAlphabet {
+    fn eq(&self, other: &CustomAlphabet) -> bool {
+        self.symbols[..] == other.symbols[..] && self.aliases[..] == other.aliases[..]
+    }
+}
+
+impl Eq for CustomAlphabet {}
+
+impl Hash for CustomAlphabet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.symbols[..].hash(state);
+        self.aliases[..].hash(state)
+    }
+}
+
+impl fmt::Debug for CustomAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // all symbols are ASCII, so this can't fail
+        let symbols = str::from_utf8(&self.symbols).map_err(|_| fmt::Error)?;
+
+        let mut debug = f.debug_tuple("CustomAlphabet");
+        let _ = debug.field(&symbols);
+        if let Some(aliases) = self.alias_table() {
+            let _ = debug.field(&str::from_utf8(aliases).map_err(|_| fmt::Error)?);
+        }
+        debug.finish()
+    }
+}
+
+/// Errors that can occur while creating a `CustomAlphabet`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AlphabetError {
+    /// A symbol was not an ASCII byte. The offending byte is provided.
+    NonAsciiByte(u8),
+    /// A symbol occurred more than once. The offending byte is provided.
+    DuplicatedByte(u8),
+    /// A symbol was a byte reserved for other purposes, like padding. The offending byte is
+    /// provided.
+    ReservedByte(u8),
+}
+
+impl fmt::Display for AlphabetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlphabetError::NonAsciiByte(byte) => write!(f, "Non-ASCII byte {}.", byte),
+            AlphabetError::DuplicatedByte(byte) => write!(f, "Duplicated byte {}.", byte),
+            AlphabetError::ReservedByte(byte) => write!(f, "Reserved byte {}.", byte),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for AlphabetError {
+    fn description(&self) -> &str {
+        match *self {
+            AlphabetError::NonAsciiByte(_) => "non-ascii byte",
+            AlphabetError::DuplicatedByte(_) => "duplicated byte",
+            AlphabetError::ReservedByte(_) => "reserved byte",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_config, encode_config, CharacterSet, Config, STANDARD, URL_SAFE};
+
+    #[test]
+    fn custom_alphabet_matches_builtin_tables() {
+        let standard = CustomAlphabet::new(tables::STANDARD_ENCODE).unwrap();
+        assert_eq!(&tables::STANDARD_ENCODE[..], &standard.encode_table()[..]);
+        assert_eq!(&tables::STANDARD_DECODE[..], &standard.decode_table()[..]);
+
+        let url_safe = CustomAlphabet::new(tables::URL_SAFE_ENCODE).unwrap();
+        assert_eq!(&tables::URL_SAFE_ENCODE[..], &url_safe.encode_table()[..]);
+        assert_eq!(&tables::URL_SAFE_DECODE[..], &url_safe.decode_table()[..]);
+    }
+
+    #[test]
+    fn custom_alphabet_roundtrip_matches_builtin_config() {
+        let config = Config::new(
+            CharacterSet::Custom(CustomAlphabet::new(tables::URL_SAFE_ENCODE).unwrap()),
+            true,
+        );
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        let encoded = encode_config(&bytes, config);
+        assert_eq!(encode_config(&bytes, URL_SAFE), encoded);
+        assert_eq!(bytes, decode_config(&encoded, config).unwrap());
+        // standard-only symbols are not in the alphabet
+        assert!(decode_config(encode_config(&bytes, STANDARD), config).is_err());
+    }
+
+    #[test]
+    fn custom_alphabet_rejects_duplicated_byte() {
+        let mut symbols = *tables::STANDARD_ENCODE;
+        symbols[63] = b'A';
+
+        assert_eq!(
+            AlphabetError::DuplicatedByte(b'A'),
+            CustomAlphabet::new(&symbols).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn custom_alphabet_rejects_non_ascii_byte() {
+        let mut symbols = *tables::STANDARD_ENCODE;
+        symbols[10] = 0xC3;
+
+        assert_eq!(
+            AlphabetError::NonAsciiByte(0xC3),
+            CustomAlphabet::new(&symbols).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn custom_alphabet_rejects_padding_byte() {
+        let mut symbols = *tables::STANDARD_ENCODE;
+        symbols[0] = b'=';
+
+        assert_eq!(
+            AlphabetError::ReservedByte(b'='),
+            CustomAlphabet::new(&symbols).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn custom_alphabet_aliases_decode_but_dont_encode() {
+        let symbols = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$%&'()*+,-./:;<>?@[]^_{|}~";
+        let mut lowercase = *symbols;
+        lowercase.make_ascii_lowercase();
+        let config = Config::new(
+            CharacterSet::Custom(CustomAlphabet::with_aliases(symbols, &lowercase).unwrap()),
+            true,
+        );
+
+        assert_eq!(decode_config("ABCD", config), decode_config("abcd", config));
+        assert_eq!(decode_config("ABCD", config), decode_config("aBcD", config));
+        // the constant time decoder has to know about the aliases too
+        assert_eq!(
+            decode_config("ABCD", config),
+            decode_config("abcd", config.decode_constant_time(true))
+        );
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded = encode_config(&bytes, config);
+        assert!(!encoded.bytes().any(|b| b.is_ascii_lowercase()));
+        assert_eq!(bytes, decode_config(&encoded, config).unwrap());
+        assert_eq!(
+            bytes,
+            decode_config(encoded.to_ascii_lowercase(), config).unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_alphabet_rejects_bad_aliases() {
+        let mut aliases = *tables::STANDARD_ENCODE;
+        aliases[0] = b'B';
+        assert_eq!(
+            AlphabetError::DuplicatedByte(b'B'),
+            CustomAlphabet::with_aliases(tables::STANDARD_ENCODE, &aliases).unwrap_err()
+        );
+
+        let mut aliases = *tables::STANDARD_ENCODE;
+        aliases[0] = b'-';
+        aliases[1] = b'-';
+        assert_eq!(
+            AlphabetError::DuplicatedByte(b'-'),
+            CustomAlphabet::with_aliases(tables::STANDARD_ENCODE, &aliases).unwrap_err()
+        );
+
+        aliases[1] = b'=';
+        assert_eq!(
+            AlphabetError::ReservedByte(b'='),
+            CustomAlphabet::with_aliases(tables::STANDARD_ENCODE, &aliases).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn custom_alphabet_debug_shows_symbols() {
+        let alphabet = CustomAlphabet::new(tables::STANDARD_ENCODE).unwrap();
+
+        assert_eq!(
+            "CustomAlphabet(\"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/\")",
+            format!("{:?}", alphabet)
+        );
+    }
+}
@@ -1,12 +1,10 @@
 use crate::{
-    encode::{add_padding, encode_to_slice},
+    encode::{add_padding, encode_with_table},
     Config,
 };
 #[cfg(any(feature = "alloc", feature = "std", test))]
 use alloc::string::String;
-use core::cmp;
-#[cfg(any(feature = "alloc", feature = "std", test))]
-use core::str;
+use core::{cmp, fmt, str};
 
 /// The output mechanism for ChunkedEncoder's encoded bytes.
 pub trait Sink {
@@ -44,7 +42,7 @@ impl ChunkedEncoder {
 
             let chunk = &bytes[input_index..(input_index + input_chunk_len)];
 
-            let mut b64_bytes_written = encode_to_slice(chunk, &mut encode_buf, encode_table);
+            let mut b64_bytes_written = encode_with_table(chunk, &mut encode_buf, encode_table);
 
             input_index += input_chunk_len;
             let more_input_left = input_index < bytes.len();
@@ -52,7 +50,11 @@ impl ChunkedEncoder {
             if self.config.pad && !more_input_left {
                 // no more input, add padding if needed. Buffer will have room because
                 // max_input_length leaves room for it.
-                b64_bytes_written += add_padding(bytes.len(), &mut encode_buf[b64_bytes_written..]);
+                b64_bytes_written += add_padding(
+                    bytes.len(),
+                    self.config.pad_byte,
+                    &mut encode_buf[b64_bytes_written..],
+                );
             }
 
             sink.write_encoded_bytes(&encode_buf[0..b64_bytes_written])?;
@@ -83,6 +85,28 @@ fn max_input_length(encoded_buf_len: usize, config: Config) -> usize {
     (effective_buf_len / 4) * 3
 }
 
+// Writes to anything that implements fmt::Write, including a Formatter
+pub(crate) struct FmtSink<'a, W: fmt::Write + ?Sized> {
+    w: &'a mut W,
+}
+
+impl<'a, W: fmt::Write + ?Sized> FmtSink<'a, W> {
+    pub(crate) fn new(w: &mut W) -> FmtSink<'_, W> {
+        FmtSink { w }
+    }
+}
+
+impl<'a, W: fmt::Write + ?Sized> Sink for FmtSink<'a, W> {
+    type Error = fmt::Error;
+
+    fn write_encoded_bytes(&mut self, encoded: &[u8]) -> Result<(), Self::Error> {
+        // Avoid unsafe. If max performance is needed, write your own display wrapper that uses
+        // unsafe here to gain about 10-15%.
+        self.w
+            .write_str(str::from_utf8(encoded).expect("base64 data was not utf8"))
+    }
+}
+
 // A really simple sink that just appends to a string
 #[cfg(any(feature = "alloc", feature = "std", test))]
 pub(crate) struct StringSink<'a> {
@@ -91,7 +115,7 @@ pub(crate) struct StringSink<'a> {
 
 #[cfg(any(feature = "alloc", feature = "std", test))]
 impl<'a> StringSink<'a> {
-    pub(crate) fn new(s: &mut String) -> StringSink {
+    pub(crate) fn new(s: &mut String) -> StringSink<'_> {
         StringSink { string: s }
     }
 }
@@ -108,6 +132,7 @@ impl<'a> Sink for StringSink<'a> {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_return)]
 pub mod tests {
     use super::*;
     use crate::{encode_config_buf, tests::random_config, CharacterSet, STANDARD};
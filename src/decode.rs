@@ -1,10 +1,13 @@
-use crate::{tables, Config, PAD_BYTE};
+use crate::{tables, Config};
 
 #[cfg(any(feature = "alloc", feature = "std", test))]
 use crate::STANDARD;
 #[cfg(any(feature = "alloc", feature = "std", test))]
-use alloc::vec::Vec;
-use core::fmt;
+use alloc::{string::String, vec::Vec};
+use core::iter;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+use core::str;
+use core::{cmp, fmt};
 #[cfg(any(feature = "std", test))]
 use std::error;
 
@@ -39,18 +42,95 @@ pub enum DecodeError {
     /// Unlike InvalidByte, which reports symbols that aren't in the alphabet, this error is for
     /// symbols that are in the alphabet but represent nonsensical encodings.
     InvalidLastSymbol(usize, u8),
+    /// The input's padding is malformed: it isn't at the end of the input, follows fewer than two
+    /// symbols in the last quad, or there is too much of it.
+    InvalidPadding,
+    /// The input would decode to more bytes than allowed by `Config::decode_max_len`.
+    TooLong,
+    /// The input decoded to a different number of bytes than was expected. See
+    /// `decode_config_exact` and `decode_words`.
+    UnexpectedLength,
+    /// The provided output slice is too small to hold the decoded data.
+    /// See `max_decoded_len` for sizing an output slice ahead of time.
+    OutputSliceTooSmall,
+}
+
+impl DecodeError {
+    /// The kind of error this is, without the offset or byte that some variants carry.
+    ///
+    /// Useful for counting or grouping errors by category, e.g. in logs or metrics.
+    pub fn kind(&self) -> DecodeErrorKind {
+        match *self {
+            DecodeError::InvalidByte(_, _) => DecodeErrorKind::InvalidByte,
+            DecodeError::InvalidLength => DecodeErrorKind::InvalidLength,
+            DecodeError::InvalidLastSymbol(_, _) => DecodeErrorKind::InvalidLastSymbol,
+            DecodeError::InvalidPadding => DecodeErrorKind::InvalidPadding,
+            DecodeError::TooLong => DecodeErrorKind::TooLong,
+            DecodeError::UnexpectedLength => DecodeErrorKind::UnexpectedLength,
+            DecodeError::OutputSliceTooSmall => DecodeErrorKind::OutputSliceTooSmall,
+        }
+    }
+}
+
+/// The kind of a `DecodeError`, as returned by `DecodeError::kind`.
+///
+/// There is one kind for each `DecodeError` variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DecodeErrorKind {
+    /// See `DecodeError::InvalidByte`.
+    InvalidByte,
+    /// See `DecodeError::InvalidLength`.
+    InvalidLength,
+    /// See `DecodeError::InvalidLastSymbol`.
+    InvalidLastSymbol,
+    /// See `DecodeError::InvalidPadding`.
+    InvalidPadding,
+    /// See `DecodeError::TooLong`.
+    TooLong,
+    /// See `DecodeError::UnexpectedLength`.
+    UnexpectedLength,
+    /// See `DecodeError::OutputSliceTooSmall`.
+    OutputSliceTooSmall,
+}
+
+impl DecodeErrorKind {
+    /// A short, fixed description of this kind of error, e.g. `"invalid byte"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DecodeErrorKind::InvalidByte => "invalid byte",
+            DecodeErrorKind::InvalidLength => "invalid length",
+            DecodeErrorKind::InvalidLastSymbol => "invalid last symbol",
+            DecodeErrorKind::InvalidPadding => "invalid padding",
+            DecodeErrorKind::TooLong => "too long",
+            DecodeErrorKind::UnexpectedLength => "unexpected length",
+            DecodeErrorKind::OutputSliceTooSmall => "output slice too small",
+        }
+    }
+}
+
+impl fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            DecodeError::InvalidByte(index, byte) if byte.is_ascii_graphic() || byte == b' ' => {
+                write!(f, "Invalid byte '{}', offset {}.", byte as char, index)
+            }
             DecodeError::InvalidByte(index, byte) => {
-                write!(f, "Invalid byte {}, offset {}.", byte, index)
+                write!(f, "Invalid byte {:#04x}, offset {}.", byte, index)
             }
             DecodeError::InvalidLength => write!(f, "Encoded text cannot have a 6-bit remainder."),
             DecodeError::InvalidLastSymbol(index, byte) => {
                 write!(f, "Invalid last symbol {}, offset {}.", byte, index)
             }
+            DecodeError::InvalidPadding => write!(f, "Invalid padding."),
+            DecodeError::TooLong => write!(f, "Decoded data would be too long."),
+            DecodeError::UnexpectedLength => write!(f, "Decoded data is not the expected length."),
+            DecodeError::OutputSliceTooSmall => write!(f, "Output slice too small."),
         }
     }
 }
@@ -58,11 +138,7 @@ impl fmt::Display for DecodeError {
 #[cfg(any(feature = "std", test))]
 impl error::Error for DecodeError {
     fn description(&self) -> &str {
-        match *self {
-            DecodeError::InvalidByte(_, _) => "invalid byte",
-            DecodeError::InvalidLength => "invalid length",
-            DecodeError::InvalidLastSymbol(_, _) => "invalid last symbol",
-        }
+        self.kind().as_str()
     }
 
     fn cause(&self) -> Option<&dyn error::Error> {
@@ -107,15 +183,139 @@ pub fn decode<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, DecodeError> {
 ///```
 #[cfg(any(feature = "alloc", feature = "std", test))]
 pub fn decode_config<T: AsRef<[u8]>>(input: T, config: Config) -> Result<Vec<u8>, DecodeError> {
-    let mut buffer = Vec::<u8>::with_capacity(input.as_ref().len() * 4 / 3);
+    // decode_config_buf sizes the buffer, taking Config::decode_max_len into account
+    let mut buffer = Vec::<u8>::new();
 
     decode_config_buf(input, config, &mut buffer).map(|_| buffer)
 }
 
+///Decode from string reference as octets, requiring that they be exactly `expected_len` bytes.
+///Returns a Result containing a Vec<u8>.
+///
+///This is for formats that store the length of the data separately from its base64, often
+///without padding. Input that's been cut short by a whole quad or more, which would otherwise
+///decode just fine, is caught as `DecodeError::UnexpectedLength`. No more than `expected_len`
+///bytes are allocated, however long the input is.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let bytes = base64::decode_config_exact("aGVsbG8", base64::STANDARD_NO_PAD, 5).unwrap();
+///    assert_eq!(b"hello", &bytes[..]);
+///
+///    assert_eq!(
+///        Err(base64::DecodeError::UnexpectedLength),
+///        base64::decode_config_exact("aGVs", base64::STANDARD_NO_PAD, 5)
+///    );
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn decode_config_exact<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+    expected_len: usize,
+) -> Result<Vec<u8>, DecodeError> {
+    // anything longer than expected is wrong anyway, so stop there, unless the config stops sooner
+    let max_len = match config.decode_max_len {
+        Some(max_len) if max_len < expected_len => max_len,
+        _ => expected_len,
+    };
+
+    let decoded =
+        decode_config(input, config.decode_max_len(Some(max_len))).map_err(|e| match e {
+            DecodeError::TooLong if max_len == expected_len => DecodeError::UnexpectedLength,
+            _ => e,
+        })?;
+
+    if decoded.len() != expected_len {
+        return Err(DecodeError::UnexpectedLength);
+    }
+
+    Ok(decoded)
+}
+
+///Decode base64 that encodes UTF-8 text into a String.
+///Returns a Result containing the String, or an error if either the base64 or the UTF-8 is
+///invalid.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let text = base64::decode_config_str("aGVsbG8gd29ybGQ=", base64::STANDARD).unwrap();
+///    assert_eq!("hello world", text);
+///
+///    assert!(base64::decode_config_str("_w==", base64::URL_SAFE).is_err());
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn decode_config_str<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+) -> Result<String, DecodeStrError> {
+    let bytes = decode_config(input, config).map_err(DecodeStrError::Decode)?;
+
+    // reuses the decoded buffer rather than copying it
+    String::from_utf8(bytes).map_err(|e| DecodeStrError::InvalidUtf8(e.utf8_error()))
+}
+
+/// Errors that can occur while decoding to a `String`.
+#[cfg(any(feature = "alloc", feature = "std", test))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeStrError {
+    /// The input was not valid base64.
+    Decode(DecodeError),
+    /// The decoded bytes were not valid UTF-8.
+    InvalidUtf8(str::Utf8Error),
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl fmt::Display for DecodeStrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeStrError::Decode(ref e) => write!(f, "Invalid base64: {}", e),
+            DecodeStrError::InvalidUtf8(ref e) => write!(f, "Decoded bytes are not UTF-8: {}", e),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for DecodeStrError {
+    fn description(&self) -> &str {
+        match *self {
+            DecodeStrError::Decode(_) => "invalid base64",
+            DecodeStrError::InvalidUtf8(_) => "invalid utf-8",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            DecodeStrError::Decode(ref e) => Some(e),
+            DecodeStrError::InvalidUtf8(ref e) => Some(e),
+        }
+    }
+}
+
 ///Decode from string reference as octets.
 ///Writes into the supplied buffer to avoid allocation.
 ///Returns a Result containing an empty tuple, aka ().
 ///
+///Decoded bytes are appended to `buffer`. If decoding fails, `buffer` is truncated back to the
+///length it had before the call, so what was already in it is kept and nothing partially decoded
+///is left after it.
+///
+///`buffer` is usually a `Vec<u8>`, but can be anything that implements `DecodeBuffer`, like a
+///buffer with inline storage for short values.
+///
+///A `Vec<u8>`'s capacity is never given back, whether decoding succeeds or fails, so clearing and
+///reusing one buffer for inputs of similar size only allocates until it's big enough for them.
+///`Decoder` does that bookkeeping for you.
+///
 ///# Example
 ///
 ///```rust
@@ -133,482 +333,2019 @@ pub fn decode_config<T: AsRef<[u8]>>(input: T, config: Config) -> Result<Vec<u8>
 ///    println!("{:?}", buffer);
 ///}
 ///```
-#[cfg(any(feature = "alloc", feature = "std", test))]
-pub fn decode_config_buf<T: AsRef<[u8]>>(
+pub fn decode_config_buf<T: AsRef<[u8]>, B: DecodeBuffer + ?Sized>(
     input: T,
     config: Config,
-    buffer: &mut Vec<u8>,
+    buffer: &mut B,
 ) -> Result<(), DecodeError> {
-    let input_bytes = input.as_ref();
+    decode_into_buffer(input.as_ref(), config, buffer).map(|_| ())
+}
+
+/// A growable buffer that `decode_config_buf` can append decoded bytes to.
+///
+/// This is implemented for `Vec<u8>`. Implement it for other buffer types, like ones that store
+/// short contents inline, to decode into them directly.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// use base64::DecodeBuffer;
+///
+/// /// Holds up to 16 bytes without allocating.
+/// struct InlineBuf {
+///     bytes: [u8; 16],
+///     len: usize,
+/// }
+///
+/// impl DecodeBuffer for InlineBuf {
+///     fn as_slice(&self) -> &[u8] {
+///         &self.bytes[..self.len]
+///     }
+///
+///     fn as_mut_slice(&mut self) -> &mut [u8] {
+///         &mut self.bytes[..self.len]
+///     }
+///
+///     fn reserve(&mut self, additional: usize) {
+///         assert!(additional <= self.bytes.len() - self.len, "InlineBuf is full");
+///     }
+///
+///     fn push(&mut self, byte: u8) {
+///         self.bytes[self.len] = byte;
+///         self.len += 1;
+///     }
+///
+///     fn truncate(&mut self, len: usize) {
+///         self.len = self.len.min(len);
+///     }
+/// }
+///
+/// fn main() {
+///     let mut buf = InlineBuf { bytes: [0; 16], len: 0 };
+///     base64::decode_config_buf("aGVsbG8=", base64::STANDARD, &mut buf).unwrap();
+///     assert_eq!(b"hello", buf.as_slice());
+/// }
+/// ```
+pub trait DecodeBuffer {
+    /// The bytes in the buffer.
+    fn as_slice(&self) -> &[u8];
+
+    /// The bytes in the buffer, mutably.
+    fn as_mut_slice(&mut self) -> &mut [u8];
+
+    /// Make room for at least `additional` more bytes.
+    fn reserve(&mut self, additional: usize);
+
+    /// Append `byte` to the end of the buffer.
+    fn push(&mut self, byte: u8);
+
+    /// Shorten the buffer to `len` bytes. Does nothing if it's already no longer than that.
+    fn truncate(&mut self, len: usize);
+
+    /// Append `additional` zeroes to the end of the buffer, for decoding to overwrite.
+    ///
+    /// The default reserves room and pushes them one at a time; override it if the buffer has a
+    /// faster way.
+    fn extend_zeroed(&mut self, additional: usize) {
+        self.reserve(additional);
+        for _ in 0..additional {
+            self.push(0);
+        }
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl DecodeBuffer for Vec<u8> {
+    fn as_slice(&self) -> &[u8] {
+        self
+    }
 
-    let starting_output_len = buffer.len();
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self
+    }
 
-    let num_chunks = num_chunks(input_bytes);
-    let decoded_len_estimate = num_chunks
-        .checked_mul(DECODED_CHUNK_LEN)
-        .and_then(|p| p.checked_add(starting_output_len))
-        .expect("Overflow when calculating output buffer length");
-    buffer.resize(decoded_len_estimate, 0);
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
 
-    let bytes_written;
-    {
-        let buffer_slice = &mut buffer.as_mut_slice()[starting_output_len..];
-        bytes_written = decode_helper(input_bytes, num_chunks, config, buffer_slice)?;
+    fn push(&mut self, byte: u8) {
+        Vec::push(self, byte)
     }
 
-    buffer.truncate(starting_output_len + bytes_written);
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len)
+    }
 
-    Ok(())
+    fn extend_zeroed(&mut self, additional: usize) {
+        let new_len = self
+            .len()
+            .checked_add(additional)
+            .expect("Overflow when calculating output buffer length");
+        self.resize(new_len, 0)
+    }
 }
 
-/// Decode the input into the provided output slice.
+/// Decodes with a fixed `Config` into a buffer that's reused between calls, so decoding many
+/// payloads of similar size doesn't allocate a new `Vec` each time.
 ///
-/// This will not write any bytes past exactly what is decoded (no stray garbage bytes at the end).
+/// This is the same as calling `decode_config_buf` with a `Vec<u8>` that's cleared each time, but
+/// without having to keep track of the buffer.
 ///
-/// If you don't know ahead of time what the decoded length should be, size your buffer with a
-/// conservative estimate for the decoded length of an input: 3 bytes of output for every 4 bytes of
-/// input, rounded up, or in other words `(input_len + 3) / 4 * 3`.
+/// # Example
 ///
-/// If the slice is not large enough, this will panic.
-pub fn decode_config_slice<T: AsRef<[u8]>>(
-    input: T,
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut decoder = base64::Decoder::new(base64::STANDARD);
+///
+///     for (input, expected) in [("aGVsbG8=", &b"hello"[..]), ("d29ybGQ=", &b"world"[..])].iter() {
+///         assert_eq!(Ok(*expected), decoder.decode(input));
+///     }
+///     assert_eq!(Err(base64::DecodeError::InvalidByte(0, b'*')), decoder.decode("*"));
+/// }
+/// ```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+#[derive(Clone, Debug)]
+pub struct Decoder {
     config: Config,
-    output: &mut [u8],
-) -> Result<usize, DecodeError> {
-    let input_bytes = input.as_ref();
+    buf: Vec<u8>,
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl Decoder {
+    /// Create a `Decoder` that will decode with the provided config.
+    pub fn new(config: Config) -> Decoder {
+        Decoder {
+            config,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Decode `input`, returning the decoded bytes.
+    ///
+    /// The returned `&[u8]` borrows the `Decoder`'s buffer, which is overwritten by the next call.
+    pub fn decode<T: AsRef<[u8]>>(&mut self, input: T) -> Result<&[u8], DecodeError> {
+        self.buf.clear();
+        decode_config_buf(input, self.config, &mut self.buf)?;
+
+        Ok(&self.buf)
+    }
 
-    decode_helper(input_bytes, num_chunks(input_bytes), config, output)
+    /// Release the memory held by the buffer, e.g. after decoding an unusually large input.
+    pub fn reset(&mut self) {
+        self.buf = Vec::new();
+    }
 }
 
-/// Return the number of input chunks (including a possibly partial final chunk) in the input
-fn num_chunks(input: &[u8]) -> usize {
-    input
-        .len()
-        .checked_add(INPUT_CHUNK_LEN - 1)
-        .expect("Overflow when calculating number of chunks in input")
-        / INPUT_CHUNK_LEN
+///Decode the base64 in `buffer` into the front of the same `buffer`, then truncate it to the
+///decoded bytes.
+///Returns a Result containing the decoded length.
+///
+///Decoded data is always shorter than its encoding, so this never allocates. It accepts and
+///rejects the same input as `decode_config`, with the same errors. On error, `buffer` keeps its
+///length but its contents are unspecified.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let mut buffer = b"aGVsbG8gd29ybGQ=".to_vec();
+///    assert_eq!(Ok(11), base64::decode_in_place(&mut buffer, base64::STANDARD));
+///    assert_eq!(b"hello world", &buffer[..]);
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn decode_in_place(buffer: &mut Vec<u8>, config: Config) -> Result<usize, DecodeError> {
+    let (input_len, escaped_padding) = {
+        let input = trim_trailing_whitespace(buffer, config);
+        let (input, escaped_padding, _) = trim_trailing_padding(input, config);
+        (input.len(), escaped_padding)
+    };
+    // each escaped padding byte is longer than the byte it stands for, so there's room to unescape it
+    for b in &mut buffer[input_len..(input_len + escaped_padding)] {
+        *b = config.pad_byte;
+    }
+    let input_len = input_len + escaped_padding;
+
+    let written = if config.decode_ignore_invalid {
+        decode_in_place_bytewise(buffer, input_len, config)?
+    } else {
+        decode_in_place_chunks(buffer, input_len, config)?
+    };
+
+    buffer.truncate(written);
+    Ok(written)
 }
 
-/// Helper to avoid duplicating num_chunks calculation, which is costly on short inputs.
-/// Returns the number of bytes written, or an error.
-// We're on the fragile edge of compiler heuristics here. If this is not inlined, slow. If this is
-// inlined(always), a different slow. plain ol' inline makes the benchmarks happiest at the moment,
-// but this is fragile and the best setting changes with only minor code modifications.
-#[inline]
-fn decode_helper(
-    input: &[u8],
-    num_chunks: usize,
+/// Decode `buffer[..input_len]` in place a chunk at a time, split the same way `decode_helper`
+/// splits it so that the errors are the same: every chunk but the last must be all symbols, and
+/// the last, which may be partial or padded, is decoded on its own.
+#[cfg(any(feature = "alloc", feature = "std", test))]
+fn decode_in_place_chunks(
+    buffer: &mut [u8],
+    input_len: usize,
     config: Config,
-    output: &mut [u8],
 ) -> Result<usize, DecodeError> {
-    let char_set = config.char_set;
-    let decode_table = char_set.decode_table();
+    let mut custom_decode_table = None;
+    let decode_table = config.char_set.decode_table(&mut custom_decode_table);
 
-    let remainder_len = input.len() % INPUT_CHUNK_LEN;
-
-    // Because the fast decode loop writes in groups of 8 bytes (unrolled to
-    // CHUNKS_PER_FAST_LOOP_BLOCK times 8 bytes, where possible) and outputs 8 bytes at a time (of
-    // which only 6 are valid data), we need to be sure that we stop using the fast decode loop
-    // soon enough that there will always be 2 more bytes of valid data written after that loop.
-    let trailing_bytes_to_skip = match remainder_len {
-        // if input is a multiple of the chunk size, ignore the last chunk as it may have padding,
-        // and the fast decode logic cannot handle padding
-        0 => INPUT_CHUNK_LEN,
-        // 1 and 5 trailing bytes are illegal: can't decode 6 bits of input into a byte
-        1 | 5 => {
-            // trailing whitespace is so common that it's worth it to check the last byte to
-            // possibly return a better error message
-            if let Some(b) = input.last() {
-                if *b != PAD_BYTE && decode_table[*b as usize] == tables::INVALID_VALUE {
-                    return Err(DecodeError::InvalidByte(input.len() - 1, *b));
-                }
-            }
+    if input_len % 4 == 1 {
+        return Err(invalid_length_error(
+            &buffer[..input_len],
+            config,
+            decode_table,
+        ));
+    }
 
-            return Err(DecodeError::InvalidLength);
+    let last_chunk_start = input_len.saturating_sub(1) / INPUT_CHUNK_LEN * INPUT_CHUNK_LEN;
+    let mut written = 0;
+    for chunk_start in (0..last_chunk_start).step_by(INPUT_CHUNK_LEN) {
+        if exceeds_max_len(written + DECODED_CHUNK_LEN, config) {
+            return Err(DecodeError::TooLong);
         }
-        // This will decode to one output byte, which isn't enough to overwrite the 2 extra bytes
-        // written by the fast decode loop. So, we have to ignore both these 2 bytes and the
-        // previous chunk.
-        2 => INPUT_CHUNK_LEN + 2,
-        // If this is 3 unpadded chars, then it would actually decode to 2 bytes. However, if this
-        // is an erroneous 2 chars + 1 pad char that would decode to 1 byte, then it should fail
-        // with an error, not panic from going past the bounds of the output slice, so we let it
-        // use stage 3 + 4.
-        3 => INPUT_CHUNK_LEN + 3,
-        // This can also decode to one output byte because it may be 2 input chars + 2 padding
-        // chars, which would decode to 1 byte.
-        4 => INPUT_CHUNK_LEN + 4,
-        // Everything else is a legal decode len (given that we don't require padding), and will
-        // decode to at least 2 bytes of output.
-        _ => remainder_len,
-    };
 
-    // rounded up to include partial chunks
-    let mut remaining_chunks = num_chunks;
+        // The output may overlap the chunk, so decode from a copy. The output is always behind
+        // the chunk, so the 2 extra bytes `decode_chunk` writes only land on input that's
+        // already been decoded.
+        let mut chunk = [0_u8; INPUT_CHUNK_LEN];
+        chunk.copy_from_slice(&buffer[chunk_start..(chunk_start + INPUT_CHUNK_LEN)]);
+        let output = &mut buffer[written..(written + INPUT_CHUNK_LEN)];
+
+        let decoded_constant_time = config.decode_constant_time
+            && decode_constant_time(&chunk, 0, config, output).map_or(false, |m| !m.is_padded());
+        if !decoded_constant_time {
+            decode_chunk(&chunk, chunk_start, decode_table, output)
+                .map_err(|e| adjust_error(e, config, false))?;
+        }
 
-    let mut input_index = 0;
-    let mut output_index = 0;
+        written += DECODED_CHUNK_LEN;
+    }
 
-    {
-        let length_of_fast_decode_chunks = input.len().saturating_sub(trailing_bytes_to_skip);
+    let mut last_chunk_output = [0_u8; DECODED_CHUNK_LEN];
+    let (last_chunk_output_len, output_limited) = limit_output_len(
+        DECODED_CHUNK_LEN,
+        config.decode_max_len(config.decode_max_len.map(|max_len| max_len - written)),
+    );
+    // the end of the input has already been trimmed, and trimming again could find more
+    let last_chunk_config = config
+        .decode_allow_trailing_whitespace(false)
+        .decode_url_escaped_padding(false)
+        .decode_max_len(None);
+    let last_chunk_decoded_len = decode_config_slice(
+        &buffer[last_chunk_start..input_len],
+        last_chunk_config,
+        &mut last_chunk_output[..last_chunk_output_len],
+    )
+    .map_err(|e| match e {
+        DecodeError::OutputSliceTooSmall if output_limited => DecodeError::TooLong,
+        DecodeError::InvalidByte(i, b) => DecodeError::InvalidByte(last_chunk_start + i, b),
+        DecodeError::InvalidLastSymbol(i, b) => {
+            DecodeError::InvalidLastSymbol(last_chunk_start + i, b)
+        }
+        e => e,
+    })?;
 
-        // Fast loop, stage 1
-        // manual unroll to CHUNKS_PER_FAST_LOOP_BLOCK of u64s to amortize slice bounds checks
-        if let Some(max_start_index) = length_of_fast_decode_chunks.checked_sub(INPUT_BLOCK_LEN) {
-            while input_index <= max_start_index {
-                let input_slice = &input[input_index..(input_index + INPUT_BLOCK_LEN)];
-                let output_slice = &mut output[output_index..(output_index + DECODED_BLOCK_LEN)];
+    buffer[written..(written + last_chunk_decoded_len)]
+        .copy_from_slice(&last_chunk_output[..last_chunk_decoded_len]);
+    Ok(written + last_chunk_decoded_len)
+}
 
-                decode_chunk(
-                    &input_slice[0..],
-                    input_index,
-                    decode_table,
-                    &mut output_slice[0..],
-                )?;
-                decode_chunk(
-                    &input_slice[8..],
-                    input_index + 8,
-                    decode_table,
-                    &mut output_slice[6..],
-                )?;
-                decode_chunk(
-                    &input_slice[16..],
-                    input_index + 16,
-                    decode_table,
-                    &mut output_slice[12..],
-                )?;
-                decode_chunk(
-                    &input_slice[24..],
-                    input_index + 24,
-                    decode_table,
-                    &mut output_slice[18..],
-                )?;
+/// Decode `buffer[..input_len]` in place a byte at a time, for when skipped bytes mean quads
+/// don't line up with offsets in the input. Every decoded byte is written behind the byte being
+/// read, so nothing is overwritten before it's decoded.
+#[cfg(any(feature = "alloc", feature = "std", test))]
+fn decode_in_place_bytewise(
+    buffer: &mut [u8],
+    input_len: usize,
+    config: Config,
+) -> Result<usize, DecodeError> {
+    let mut custom_decode_table = None;
+    let decode_table = config.char_set.decode_table(&mut custom_decode_table);
 
-                input_index += INPUT_BLOCK_LEN;
-                output_index += DECODED_BLOCK_LEN - DECODED_CHUNK_SUFFIX;
-                remaining_chunks -= CHUNKS_PER_FAST_LOOP_BLOCK;
+    let mut decoder = BytewiseDecoder::default();
+    let mut written = 0;
+    {
+        let mut write = |decoded: &[u8], buffer: &mut [u8]| {
+            if exceeds_max_len(written + decoded.len(), config) {
+                return Err(DecodeError::TooLong);
             }
-        }
 
-        // Fast loop, stage 2 (aka still pretty fast loop)
-        // 8 bytes at a time for whatever we didn't do in stage 1.
-        if let Some(max_start_index) = length_of_fast_decode_chunks.checked_sub(INPUT_CHUNK_LEN) {
-            while input_index < max_start_index {
-                decode_chunk(
-                    &input[input_index..(input_index + INPUT_CHUNK_LEN)],
-                    input_index,
-                    decode_table,
-                    &mut output
-                        [output_index..(output_index + DECODED_CHUNK_LEN + DECODED_CHUNK_SUFFIX)],
-                )?;
+            buffer[written..written + decoded.len()].copy_from_slice(decoded);
+            written += decoded.len();
+            Ok(())
+        };
 
-                output_index += DECODED_CHUNK_LEN;
-                input_index += INPUT_CHUNK_LEN;
-                remaining_chunks -= 1;
-            }
+        for i in 0..input_len {
+            let b = buffer[i];
+            decoder.feed(iter::once(b), config, decode_table, |decoded| {
+                write(decoded, buffer)
+            })?;
         }
+        let _ = decoder.finish(config, |decoded| write(decoded, buffer))?;
     }
 
-    // Stage 3
-    // If input length was such that a chunk had to be deferred until after the fast loop
-    // because decoding it would have produced 2 trailing bytes that wouldn't then be
-    // overwritten, we decode that chunk here. This way is slower but doesn't write the 2
-    // trailing bytes.
-    // However, we still need to avoid the last chunk (partial or complete) because it could
-    // have padding, so we always do 1 fewer to avoid the last chunk.
-    for _ in 1..remaining_chunks {
-        decode_chunk_precise(
-            &input[input_index..],
-            input_index,
-            decode_table,
-            &mut output[output_index..(output_index + DECODED_CHUNK_LEN)],
-        )?;
+    Ok(written)
+}
 
-        input_index += INPUT_CHUNK_LEN;
-        output_index += DECODED_CHUNK_LEN;
-    }
+#[cfg(any(feature = "alloc", feature = "std", test))]
+fn exceeds_max_len(len: usize, config: Config) -> bool {
+    config.decode_max_len.map_or(false, |max_len| len > max_len)
+}
 
-    // always have one more (possibly partial) block of 8 input
-    debug_assert!(input.len() - input_index > 1 || input.is_empty());
-    debug_assert!(input.len() - input_index <= 8);
+///Decode from string reference as octets, also reporting details about the input that aren't
+///reflected in the decoded bytes, like how much padding it had.
+///Returns a Result containing a Vec<u8> and the `DecodeMetadata`.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let (bytes, metadata) =
+///        base64::decode_config_with_info("aGVsbG8gd29ybGQ=", base64::STANDARD).unwrap();
+///    assert_eq!(b"hello world", &bytes[..]);
+///    assert_eq!(1, metadata.padding_len());
+///
+///    let (_, metadata) =
+///        base64::decode_config_with_info("aGVsbG8gd29ybGQ", base64::STANDARD).unwrap();
+///    assert!(!metadata.is_padded());
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn decode_config_with_info<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+) -> Result<(Vec<u8>, DecodeMetadata), DecodeError> {
+    let mut buffer = Vec::<u8>::new();
 
-    // Stage 4
-    // Finally, decode any leftovers that aren't a complete input block of 8 bytes.
-    // Use a u64 as a stack-resident 8 byte buffer.
-    let mut leftover_bits: u64 = 0;
-    let mut morsels_in_leftover = 0;
-    let mut padding_bytes = 0;
-    let mut first_padding_index: usize = 0;
-    let mut last_symbol = 0_u8;
-    let start_of_leftovers = input_index;
-    for (i, b) in input[start_of_leftovers..].iter().enumerate() {
-        // '=' padding
-        if *b == PAD_BYTE {
-            // There can be bad padding in a few ways:
-            // 1 - Padding with non-padding characters after it
-            // 2 - Padding after zero or one non-padding characters before it
-            //     in the current quad.
-            // 3 - More than two characters of padding. If 3 or 4 padding chars
-            //     are in the same quad, that implies it will be caught by #2.
-            //     If it spreads from one quad to another, it will be caught by
-            //     #2 in the second quad.
+    decode_into_buffer(input.as_ref(), config, &mut buffer).map(|metadata| (buffer, metadata))
+}
 
-            if i % 4 < 2 {
-                // Check for case #2.
-                let bad_padding_index = start_of_leftovers
-                    + if padding_bytes > 0 {
-                        // If we've already seen padding, report the first padding index.
-                        // This is to be consistent with the faster logic above: it will report an
-                        // error on the first padding character (since it doesn't expect to see
-                        // anything but actual encoded data).
-                        first_padding_index
-                    } else {
-                        // haven't seen padding before, just use where we are now
-                        i
-                    };
-                return Err(DecodeError::InvalidByte(bad_padding_index, *b));
+///Decode base64 from an iterator of bytes, for input that isn't in a contiguous slice.
+///Returns a Result containing a Vec<u8>.
+///
+///This follows the same rules as `decode_config`, but decodes a byte at a time, so it's slower
+///than collecting the input and decoding the slice when that's an option. Which error is reported
+///for input with more than one problem may differ from `decode_config`, and
+///`Config::decode_constant_time` has no effect.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let encoded = ["aGVs", "bG8="];
+///    let bytes = base64::decode_iter(encoded.iter().flat_map(|s| s.bytes()), base64::STANDARD);
+///    assert_eq!(Ok(b"hello".to_vec()), bytes);
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn decode_iter<I: IntoIterator<Item = u8>>(
+    input: I,
+    config: Config,
+) -> Result<Vec<u8>, DecodeError> {
+    let input = input.into_iter();
+    let mut custom_decode_table = None;
+    let decode_table = config.char_set.decode_table(&mut custom_decode_table);
+
+    let (output_len_estimate, _) = limit_output_len(max_decoded_len(input.size_hint().0), config);
+    let mut buffer = Vec::with_capacity(output_len_estimate);
+
+    let _ = decode_bytewise(input, config, decode_table, |decoded| {
+        if let Some(max_len) = config.decode_max_len {
+            if max_len - buffer.len() < decoded.len() {
+                return Err(DecodeError::TooLong);
             }
+        }
 
-            if padding_bytes == 0 {
-                first_padding_index = i;
-            }
+        buffer.extend_from_slice(decoded);
+        Ok(())
+    })?;
 
-            padding_bytes += 1;
-            continue;
-        }
+    Ok(buffer)
+}
 
-        // Check for case #1.
-        // To make '=' handling consistent with the main loop, don't allow
-        // non-suffix '=' in trailing chunk either. Report error as first
-        // erroneous padding.
-        if padding_bytes > 0 {
-            return Err(DecodeError::InvalidByte(
-                start_of_leftovers + first_padding_index,
-                PAD_BYTE,
-            ));
+fn decode_into_buffer<B: DecodeBuffer + ?Sized>(
+    input: &[u8],
+    config: Config,
+    buffer: &mut B,
+) -> Result<DecodeMetadata, DecodeError> {
+    let input_bytes = trim_trailing_whitespace(input, config);
+    let trailing_whitespace = input.len() - input_bytes.len();
+    let (input_bytes, escaped_padding, trailing_padding) =
+        trim_trailing_padding(input_bytes, config);
+    let starting_output_len = buffer.as_slice().len();
+
+    let num_chunks = num_chunks(input_bytes.len() + escaped_padding);
+    // Input with a 6-bit remainder is rejected before anything is written, so don't allocate for
+    // it. Skipping invalid bytes could change the length, though.
+    let decoded_len_estimate =
+        if (input_bytes.len() + escaped_padding) % 4 == 1 && !config.decode_ignore_invalid {
+            0
+        } else {
+            num_chunks
+                .checked_mul(DECODED_CHUNK_LEN)
+                .expect("Overflow when calculating output buffer length")
+        };
+    let (output_len, output_limited) = limit_output_len(decoded_len_estimate, config);
+    // Zeroing the new space is the price of not using `unsafe`. It's a memset over memory the
+    // decode loop is about to write anyway, so it's cheap next to decoding itself: the
+    // `decode_reuse_buf` and `decode_slice` benchmarks, which differ only by it, show no consistent
+    // difference.
+    buffer.extend_zeroed(output_len);
+
+    let buffer_slice = &mut buffer.as_mut_slice()[starting_output_len..];
+    let mut metadata = match decode_helper(
+        input_bytes,
+        escaped_padding,
+        num_chunks,
+        config,
+        buffer_slice,
+    ) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            // drop the zeroes from the resize, and anything decoded before the error
+            buffer.truncate(starting_output_len);
+            return Err(adjust_error(e, config, output_limited));
         }
-        last_symbol = *b;
+    };
 
-        // can use up to 8 * 6 = 48 bits of the u64, if last chunk has no padding.
-        // To minimize shifts, pack the leftovers from left to right.
-        let shift = 64 - (morsels_in_leftover + 1) * 6;
-        // tables are all 256 elements, lookup with a u8 index always succeeds
-        let morsel = decode_table[*b as usize];
-        if morsel == tables::INVALID_VALUE {
-            return Err(DecodeError::InvalidByte(start_of_leftovers + i, *b));
-        }
+    buffer.truncate(starting_output_len + metadata.decoded_len);
+    metadata.padding_len += trailing_padding;
+    metadata.skipped_len += trailing_whitespace;
 
-        leftover_bits |= (morsel as u64) << shift;
-        morsels_in_leftover += 1;
-    }
+    Ok(metadata)
+}
 
-    let leftover_bits_ready_to_append = match morsels_in_leftover {
-        0 => 0,
-        2 => 8,
-        3 => 16,
-        4 => 24,
-        6 => 32,
-        7 => 40,
-        8 => 48,
-        _ => unreachable!(
-            "Impossible: must only have 0 to 8 input bytes in last chunk, with no invalid lengths"
-        ),
-    };
+/// Decode the input into the provided output slice.
+///
+/// This will not write any bytes past exactly what is decoded (no stray garbage bytes at the end).
+///
+/// If you don't know ahead of time what the decoded length should be, size your buffer with
+/// `max_decoded_len`, which is a conservative estimate for the decoded length of an input.
+///
+/// If the slice is not large enough, `DecodeError::OutputSliceTooSmall` is returned. In that case
+/// the contents of `output` are unspecified, though nothing past the end of the slice is touched.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let encoded = "aGVsbG8gd29ybGQ=";
+///     let mut buf = [0_u8; 16];
+///     assert!(base64::max_decoded_len(encoded.len()) <= buf.len());
+///
+///     let decoded_len = base64::decode_config_slice(encoded, base64::STANDARD, &mut buf).unwrap();
+///     assert_eq!(b"hello world", &buf[..decoded_len]);
+///
+///     let mut too_small = [0_u8; 4];
+///     assert_eq!(
+///         Err(base64::DecodeError::OutputSliceTooSmall),
+///         base64::decode_config_slice(encoded, base64::STANDARD, &mut too_small)
+///     );
+/// }
+/// ```
+pub fn decode_config_slice<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+    output: &mut [u8],
+) -> Result<usize, DecodeError> {
+    let input_bytes = trim_trailing_whitespace(input.as_ref(), config);
+    let (input_bytes, escaped_padding, _) = trim_trailing_padding(input_bytes, config);
+    let (output_len, output_limited) = limit_output_len(output.len(), config);
+
+    decode_helper(
+        input_bytes,
+        escaped_padding,
+        num_chunks(input_bytes.len() + escaped_padding),
+        config,
+        &mut output[..output_len],
+    )
+    .map(|metadata| metadata.decoded_len)
+    .map_err(|e| adjust_error(e, config, output_limited))
+}
 
-    // if there are bits set outside the bits we care about, last symbol encodes trailing bits that
-    // will not be included in the output
-    let mask = !0 >> leftover_bits_ready_to_append;
-    if !config.decode_allow_trailing_bits && (leftover_bits & mask) != 0 {
-        // last morsel is at `morsels_in_leftover` - 1
-        return Err(DecodeError::InvalidLastSymbol(
-            start_of_leftovers + morsels_in_leftover - 1,
-            last_symbol,
-        ));
+/// Decode whole quads of base64 into the supplied output buffer, without any of the handling for
+/// the end of the input that the other decoding functions do.
+/// Returns the number of bytes written, which is always 3 for every 4 bytes of input.
+///
+/// This is the lowest level decoding function, the counterpart of `encode_to_slice`, for building
+/// your own buffering on top of. Only the character set and `Config::decode_constant_time` of
+/// `config` are used. The input must be a multiple of 4 bytes long and have no padding, or
+/// `DecodeError::InvalidLength` or `DecodeError::InvalidPadding` is returned; decode the last,
+/// possibly partial or padded, quad of the input with `decode_config_slice`. `output` must be at
+/// least 3 bytes long for every 4 bytes of input, or `DecodeError::OutputSliceTooSmall` is
+/// returned.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut buf = [0; 6];
+///     assert_eq!(Ok(6), base64::decode_to_slice("aGVsbG8h", base64::STANDARD, &mut buf));
+///     assert_eq!(b"hello!", &buf);
+///
+///     assert_eq!(
+///         Err(base64::DecodeError::InvalidLength),
+///         base64::decode_to_slice("aGVsbG8", base64::STANDARD, &mut buf)
+///     );
+/// }
+/// ```
+pub fn decode_to_slice<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+    output: &mut [u8],
+) -> Result<usize, DecodeError> {
+    let input = input.as_ref();
+    if input.len() % 4 != 0 {
+        return Err(DecodeError::InvalidLength);
     }
 
-    let mut leftover_bits_appended_to_buf = 0;
-    while leftover_bits_appended_to_buf < leftover_bits_ready_to_append {
-        // `as` simply truncates the higher bits, which is what we want here
-        let selected_bits = (leftover_bits >> (56 - leftover_bits_appended_to_buf)) as u8;
-        output[output_index] = selected_bits;
-        output_index += 1;
+    let config = Config {
+        char_set: config.char_set,
+        pad_byte: config.pad_byte,
+        decode_constant_time: config.decode_constant_time,
+        ..Config::new(config.char_set, false)
+    };
 
-        leftover_bits_appended_to_buf += 8;
+    let metadata = decode_helper(input, 0, num_chunks(input.len()), config, output)
+        .map_err(|e| adjust_error(e, config, false))?;
+    if metadata.padding_len > 0 {
+        return Err(DecodeError::InvalidPadding);
     }
 
-    Ok(output_index)
+    Ok(metadata.decoded_len)
 }
 
-#[inline]
-fn write_u64(output: &mut [u8], value: u64) {
-    output[..8].copy_from_slice(&value.to_be_bytes());
+/// Details about decoded input that aren't reflected in the decoded bytes themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeMetadata {
+    decoded_len: usize,
+    padding_len: usize,
+    skipped_len: usize,
 }
 
-/// Decode 8 bytes of input into 6 bytes of output. 8 bytes of output will be written, but only the
-/// first 6 of those contain meaningful data.
-///
-/// `input` is the bytes to decode, of which the first 8 bytes will be processed.
-/// `index_at_start_of_input` is the offset in the overall input (used for reporting errors
-/// accurately)
-/// `decode_table` is the lookup table for the particular base64 alphabet.
-/// `output` will have its first 8 bytes overwritten, of which only the first 6 are valid decoded
-/// data.
-// yes, really inline (worth 30-50% speedup)
-#[inline(always)]
-fn decode_chunk(
-    input: &[u8],
-    index_at_start_of_input: usize,
-    decode_table: &[u8; 256],
-    output: &mut [u8],
-) -> Result<(), DecodeError> {
-    let mut accum: u64;
+impl DecodeMetadata {
+    /// The number of decoded bytes.
+    pub fn decoded_len(&self) -> usize {
+        self.decoded_len
+    }
 
-    let morsel = decode_table[input[0] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(index_at_start_of_input, input[0]));
+    /// The number of padding bytes at the end of the input. This is at most 2 unless
+    /// `DecodePaddingMode::IgnoreTrailing` is used.
+    pub fn padding_len(&self) -> usize {
+        self.padding_len
     }
-    accum = (morsel as u64) << 58;
 
-    let morsel = decode_table[input[1] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 1,
-            input[1],
-        ));
+    /// True if the input had any padding.
+    ///
+    /// Input whose length is a multiple of 4 never needs padding, so this will be false for it
+    /// even if it was encoded with a padding config.
+    pub fn is_padded(&self) -> bool {
+        self.padding_len > 0
     }
-    accum |= (morsel as u64) << 52;
 
-    let morsel = decode_table[input[2] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 2,
-            input[2],
-        ));
+    /// The number of input bytes that were skipped rather than decoded: bytes that are neither
+    /// symbols nor padding, with `Config::decode_ignore_invalid`, and whitespace at the end, with
+    /// `Config::decode_allow_trailing_whitespace`. It's 0 without either setting.
+    ///
+    /// A count far from what's expected, like none at all for MIME that should have line breaks,
+    /// can be a sign that the input isn't what it was taken for.
+    pub fn skipped_len(&self) -> usize {
+        self.skipped_len
     }
-    accum |= (morsel as u64) << 46;
+}
 
-    let morsel = decode_table[input[3] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 3,
-            input[3],
-        ));
+/// Returns the maximum number of bytes that base64 input of length `encoded_len` can decode to.
+///
+/// This is exact for unpadded input. For padded input it may overestimate by up to 2 bytes, since
+/// the amount of padding isn't known from the length alone. A slice of this size is always large
+/// enough for `decode_config_slice`.
+pub fn max_decoded_len(encoded_len: usize) -> usize {
+    // 1 leftover symbol can't decode to anything, 2 symbols decode to 1 byte, and 3 to 2 bytes
+    (encoded_len / 4) * 3 + (encoded_len % 4) * 3 / 4
+}
+
+/// How strictly padding is checked when decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DecodePaddingMode {
+    /// Accept input with or without padding, but any padding there is must be well-formed: only
+    /// at the end, after at least 2 symbols in the last quad, and only enough to complete it.
+    Indifferent,
+    /// Require exactly the padding an encoder configured to pad would produce.
+    RequireCanonical,
+    /// Ignore any amount of padding at the end of the input. Padding anywhere else is still an
+    /// error.
+    IgnoreTrailing,
+}
+
+/// With `Config::decode_allow_trailing_whitespace`, whitespace at the end of the input is dropped
+/// before decoding. A padding byte that's whitespace is kept, along with anything before it.
+fn trim_trailing_whitespace(input: &[u8], config: Config) -> &[u8] {
+    if !config.decode_allow_trailing_whitespace {
+        return input;
     }
-    accum |= (morsel as u64) << 40;
 
-    let morsel = decode_table[input[4] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 4,
-            input[4],
-        ));
+    let len = input
+        .iter()
+        .rposition(|&b| !b.is_ascii_whitespace() || b == config.pad_byte)
+        .map_or(0, |i| i + 1);
+
+    &input[..len]
+}
+
+/// With `Config::decode_url_escaped_padding`, up to 2 URL-escaped padding bytes, like `%3D`, at the
+/// end of the input are taken off, to be decoded as padding after the rest of it. With
+/// `DecodePaddingMode::IgnoreTrailing`, padding at the end of the input, escaped or not, is dropped
+/// before decoding.
+///
+/// Returns the input to decode, how many escaped padding bytes follow it, and how many padding
+/// bytes were dropped.
+fn trim_trailing_padding(input: &[u8], config: Config) -> (&[u8], usize, usize) {
+    let mut input = input;
+    let mut escaped_padding = 0;
+    // an alphabet with `%` in it can't tell an escape from symbols
+    if config.decode_url_escaped_padding && !config.char_set.is_valid_byte(b'%') {
+        // more than 2 padding bytes is never valid, so there's no need to look further
+        while escaped_padding < 2 && ends_with_escaped_byte(input, config.pad_byte) {
+            input = &input[..(input.len() - 3)];
+            escaped_padding += 1;
+        }
     }
-    accum |= (morsel as u64) << 34;
 
-    let morsel = decode_table[input[5] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 5,
-            input[5],
-        ));
+    if config.decode_padding_mode != DecodePaddingMode::IgnoreTrailing {
+        return (input, escaped_padding, 0);
     }
-    accum |= (morsel as u64) << 28;
 
-    let morsel = decode_table[input[6] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 6,
-            input[6],
-        ));
+    let len = input
+        .iter()
+        .rposition(|&b| b != config.pad_byte)
+        .map_or(0, |i| i + 1);
+
+    (&input[..len], 0, input.len() - len + escaped_padding)
+}
+
+/// Whether `input` ends with `byte` percent-encoded, as in a URL, in either case: `%3D` or `%3d`
+/// for `=`.
+fn ends_with_escaped_byte(input: &[u8], byte: u8) -> bool {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+    let len = input.len();
+    len >= 3
+        && input[len - 3] == b'%'
+        && input[len - 2].to_ascii_uppercase() == HEX_DIGITS[(byte >> 4) as usize]
+        && input[len - 1].to_ascii_uppercase() == HEX_DIGITS[(byte & 0xF) as usize]
+}
+
+/// Returns how much of an output buffer of length `len` may be decoded into given
+/// `Config::decode_max_len`, and whether that's less than `len`.
+fn limit_output_len(len: usize, config: Config) -> (usize, bool) {
+    match config.decode_max_len {
+        Some(max_len) if max_len < len => (max_len, true),
+        _ => (len, false),
     }
-    accum |= (morsel as u64) << 22;
+}
 
-    let morsel = decode_table[input[7] as usize];
-    if morsel == tables::INVALID_VALUE {
-        return Err(DecodeError::InvalidByte(
-            index_at_start_of_input + 7,
-            input[7],
-        ));
+/// Adjust an error from `decode_helper` to what the caller should see.
+///
+/// The chunked decode logic doesn't look for padding, so padding that's too early in the input
+/// shows up as an invalid byte there. Since the padding byte is never a symbol, that's always a
+/// padding error.
+///
+/// If the output was cut short to stay within `Config::decode_max_len`, running out of room means
+/// the input was too long.
+fn adjust_error(e: DecodeError, config: Config, output_limited: bool) -> DecodeError {
+    match e {
+        DecodeError::InvalidByte(_, b) if b == config.pad_byte => DecodeError::InvalidPadding,
+        DecodeError::OutputSliceTooSmall if output_limited => DecodeError::TooLong,
+        _ => e,
     }
-    accum |= (morsel as u64) << 16;
+}
 
-    write_u64(output, accum);
+// Errors are rare, so the decode loops build them in these cold functions. That keeps the
+// construction out of the hot code and tells the compiler which way the checks usually go.
+
+/// The error for input with a 6-bit remainder, which is rejected before decoding anything.
+#[cold]
+fn invalid_length_error(input: &[u8], config: Config, decode_table: &[u8; 256]) -> DecodeError {
+    // trailing whitespace is so common that it's worth it to check the last byte to possibly
+    // return a better error message
+    if let Some(&b) = input.last() {
+        if b != config.pad_byte && decode_table[b as usize] == tables::INVALID_VALUE {
+            return DecodeError::InvalidByte(input.len() - 1, b);
+        }
+    }
 
-    Ok(())
+    DecodeError::InvalidLength
 }
 
-/// Decode an 8-byte chunk, but only write the 6 bytes actually decoded instead of including 2
-/// trailing garbage bytes.
+#[cold]
+fn invalid_byte_error(index: usize, byte: u8) -> DecodeError {
+    DecodeError::InvalidByte(index, byte)
+}
+
+#[cold]
+fn invalid_padding_error() -> DecodeError {
+    DecodeError::InvalidPadding
+}
+
+#[cold]
+fn invalid_last_symbol_error(index: usize, byte: u8) -> DecodeError {
+    DecodeError::InvalidLastSymbol(index, byte)
+}
+
+#[cold]
+fn output_slice_too_small_error() -> DecodeError {
+    DecodeError::OutputSliceTooSmall
+}
+
+/// Return the number of input chunks (including a possibly partial final chunk) in input of length
+/// `input_len`
+fn num_chunks(input_len: usize) -> usize {
+    input_len
+        .checked_add(INPUT_CHUNK_LEN - 1)
+        .expect("Overflow when calculating number of chunks in input")
+        / INPUT_CHUNK_LEN
+}
+
+/// Helper to avoid duplicating num_chunks calculation, which is costly on short inputs.
+/// Returns the number of bytes written and padding seen, or an error.
+///
+/// `escaped_padding` padding bytes are decoded as if they followed `input`, and count towards its
+/// length. There are at most 2 of them, so they're always in the last chunk.
+// We're on the fragile edge of compiler heuristics here. If this is not inlined, slow. If this is
+// inlined(always), a different slow. plain ol' inline makes the benchmarks happiest at the moment,
+// but this is fragile and the best setting changes with only minor code modifications.
 #[inline]
-fn decode_chunk_precise(
+fn decode_helper(
     input: &[u8],
-    index_at_start_of_input: usize,
-    decode_table: &[u8; 256],
+    escaped_padding: usize,
+    num_chunks: usize,
+    config: Config,
     output: &mut [u8],
-) -> Result<(), DecodeError> {
-    let mut tmp_buf = [0_u8; 8];
+) -> Result<DecodeMetadata, DecodeError> {
+    let mut custom_decode_table = None;
+    let decode_table = config.char_set.decode_table(&mut custom_decode_table);
 
-    decode_chunk(
-        input,
-        index_at_start_of_input,
-        decode_table,
-        &mut tmp_buf[..],
-    )?;
+    if config.decode_ignore_invalid {
+        return decode_ignoring_invalid_bytes(input, escaped_padding, config, decode_table, output);
+    }
 
-    output[0..6].copy_from_slice(&tmp_buf[0..6]);
+    if config.decode_constant_time {
+        if let Some(metadata) = decode_constant_time(input, escaped_padding, config, output) {
+            return Ok(metadata);
+        }
+        // otherwise, the input is invalid or doesn't fit, so decode the usual way to find out which
+    }
 
-    Ok(())
-}
+    let input_len = input.len() + escaped_padding;
+    let remainder_len = input_len % INPUT_CHUNK_LEN;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        encode::encode_config_buf,
-        encode::encode_config_slice,
-        tests::{assert_encode_sanity, random_config},
+    // Because the fast decode loop writes in groups of 8 bytes (unrolled to
+    // CHUNKS_PER_FAST_LOOP_BLOCK times 8 bytes, where possible) and outputs 8 bytes at a time (of
+    // which only 6 are valid data), we need to be sure that we stop using the fast decode loop
+    // soon enough that there will always be 2 more bytes of valid data written after that loop.
+    let trailing_bytes_to_skip = match remainder_len {
+        // if input is a multiple of the chunk size, ignore the last chunk as it may have padding,
+        // and the fast decode logic cannot handle padding
+        0 => INPUT_CHUNK_LEN,
+        // 1 and 5 trailing bytes are illegal: can't decode 6 bits of input into a byte
+        // (or the last byte is escaped padding, which leaves nothing more specific to report)
+        1 | 5 if escaped_padding > 0 => return Err(DecodeError::InvalidLength),
+        1 | 5 => return Err(invalid_length_error(input, config, decode_table)),
+        // This will decode to one output byte, which isn't enough to overwrite the 2 extra bytes
+        // written by the fast decode loop. So, we have to ignore both these 2 bytes and the
+        // previous chunk.
+        2 => INPUT_CHUNK_LEN + 2,
+        // If this is 3 unpadded chars, then it would actually decode to 2 bytes. However, if this
+        // is an erroneous 2 chars + 1 pad char that would decode to 1 byte, then it should fail
+        // with an error, not panic from going past the bounds of the output slice, so we let it
+        // use stage 3 + 4.
+        3 => INPUT_CHUNK_LEN + 3,
+        // This can also decode to one output byte because it may be 2 input chars + 2 padding
+        // chars, which would decode to 1 byte.
+        4 => INPUT_CHUNK_LEN + 4,
+        // Everything else is a legal decode len (given that we don't require padding), and will
+        // decode to at least 2 bytes of output.
+        _ => remainder_len,
     };
 
-    use rand::{
-        distributions::{Distribution, Uniform},
-        FromEntropy, Rng,
+    // rounded up to include partial chunks
+    let mut remaining_chunks = num_chunks;
+
+    let mut input_index = 0;
+    let mut output_index = 0;
+
+    {
+        // The fast loops write 8 bytes for every 6 decoded, so they must also stop early enough
+        // that those 2 extra bytes still land inside `output`. Whatever they don't get to is
+        // handled by the slower, bounds-checked stages below.
+        let length_of_fast_decode_chunks = cmp::min(
+            input_len.saturating_sub(trailing_bytes_to_skip),
+            output.len().saturating_sub(DECODED_CHUNK_SUFFIX) / DECODED_CHUNK_LEN * INPUT_CHUNK_LEN,
+        );
+
+        // Fast loop, stage 1
+        // manual unroll to CHUNKS_PER_FAST_LOOP_BLOCK of u64s to amortize slice bounds checks
+        if let Some(max_start_index) = length_of_fast_decode_chunks.checked_sub(INPUT_BLOCK_LEN) {
+            while input_index <= max_start_index {
+                let input_slice = &input[input_index..(input_index + INPUT_BLOCK_LEN)];
+                let output_slice = &mut output[output_index..(output_index + DECODED_BLOCK_LEN)];
+
+                decode_chunk(
+                    &input_slice[0..],
+                    input_index,
+                    decode_table,
+                    &mut output_slice[0..],
+                )?;
+                decode_chunk(
+                    &input_slice[8..],
+                    input_index + 8,
+                    decode_table,
+                    &mut output_slice[6..],
+                )?;
+                decode_chunk(
+                    &input_slice[16..],
+                    input_index + 16,
+                    decode_table,
+                    &mut output_slice[12..],
+                )?;
+                decode_chunk(
+                    &input_slice[24..],
+                    input_index + 24,
+                    decode_table,
+                    &mut output_slice[18..],
+                )?;
+
+                input_index += INPUT_BLOCK_LEN;
+                output_index += DECODED_BLOCK_LEN - DECODED_CHUNK_SUFFIX;
+                remaining_chunks -= CHUNKS_PER_FAST_LOOP_BLOCK;
+            }
+        }
+
+        // Fast loop, stage 2 (aka still pretty fast loop)
+        // 8 bytes at a time for whatever we didn't do in stage 1.
+        if let Some(max_start_index) = length_of_fast_decode_chunks.checked_sub(INPUT_CHUNK_LEN) {
+            while input_index < max_start_index {
+                decode_chunk(
+                    &input[input_index..(input_index + INPUT_CHUNK_LEN)],
+                    input_index,
+                    decode_table,
+                    &mut output
+                        [output_index..(output_index + DECODED_CHUNK_LEN + DECODED_CHUNK_SUFFIX)],
+                )?;
+
+                output_index += DECODED_CHUNK_LEN;
+                input_index += INPUT_CHUNK_LEN;
+                remaining_chunks -= 1;
+            }
+        }
+    }
+
+    // Stage 3
+    // If input length was such that a chunk had to be deferred until after the fast loop
+    // because decoding it would have produced 2 trailing bytes that wouldn't then be
+    // overwritten, we decode that chunk here. This way is slower but doesn't write the 2
+    // trailing bytes.
+    // However, we still need to avoid the last chunk (partial or complete) because it could
+    // have padding, so we always do 1 fewer to avoid the last chunk.
+    for _ in 1..remaining_chunks {
+        if output.len() - output_index < DECODED_CHUNK_LEN {
+            return Err(output_slice_too_small_error());
+        }
+
+        decode_chunk_precise(
+            &input[input_index..],
+            input_index,
+            decode_table,
+            &mut output[output_index..(output_index + DECODED_CHUNK_LEN)],
+        )?;
+
+        input_index += INPUT_CHUNK_LEN;
+        output_index += DECODED_CHUNK_LEN;
+    }
+
+    // always have one more (possibly partial) block of 8 input
+    debug_assert!(input_len - input_index > 1 || input_len == 0);
+    debug_assert!(input_len - input_index <= 8);
+
+    // Stage 4
+    // Finally, decode any leftovers that aren't a complete input block of 8 bytes.
+    // Use a u64 as a stack-resident 8 byte buffer.
+    let mut leftover_bits: u64 = 0;
+    let mut morsels_in_leftover = 0;
+    let mut padding_bytes = 0;
+    let mut last_symbol = 0_u8;
+    let start_of_leftovers = input_index;
+
+    // Short inputs are mostly leftovers, so decode whole quads of symbols here at once rather than
+    // a byte at a time. Once a quad has anything but symbols in it, or there's less than a quad
+    // left, the loop below takes over, since it knows about padding and which error to report. A
+    // padded last quad is left to it from the start, so checking it here isn't wasted work.
+    let leftovers = &input[start_of_leftovers..];
+    let quads_end = if leftovers.last() == Some(&config.pad_byte) {
+        (leftovers.len() - 1) / 4 * 4
+    } else {
+        leftovers.len()
+    };
+    let mut quads_len = 0;
+    for quad in leftovers[..quads_end].chunks_exact(4) {
+        let morsels = [
+            decode_table[quad[0] as usize],
+            decode_table[quad[1] as usize],
+            decode_table[quad[2] as usize],
+            decode_table[quad[3] as usize],
+        ];
+        // symbols are at most 63, so only INVALID_VALUE sets the top 2 bits
+        if (morsels[0] | morsels[1] | morsels[2] | morsels[3]) & 0xC0 != 0 {
+            break;
+        }
+
+        let quad_bits = (morsels[0] as u64) << 18
+            | (morsels[1] as u64) << 12
+            | (morsels[2] as u64) << 6
+            | morsels[3] as u64;
+        leftover_bits |= quad_bits << (64 - (morsels_in_leftover + 4) * 6);
+        morsels_in_leftover += 4;
+        last_symbol = quad[3];
+        quads_len += 4;
+    }
+
+    for (i, b) in (quads_len..).zip(&leftovers[quads_len..]) {
+        // '=' padding (or whatever the configured padding byte is)
+        if *b == config.pad_byte {
+            // There can be bad padding in a few ways:
+            // 1 - Padding with non-padding characters after it
+            // 2 - Padding after zero or one non-padding characters before it
+            //     in the current quad.
+            // 3 - More than two characters of padding. If 3 or 4 padding chars
+            //     are in the same quad, that implies it will be caught by #2.
+            //     If it spreads from one quad to another, it will be caught by
+            //     #2 in the second quad.
+
+            if i % 4 < 2 {
+                // Check for case #2.
+                return Err(invalid_padding_error());
+            }
+
+            padding_bytes += 1;
+            continue;
+        }
+
+        // Check for case #1.
+        // To make '=' handling consistent with the main loop, don't allow
+        // non-suffix '=' in trailing chunk either.
+        if padding_bytes > 0 {
+            return Err(invalid_padding_error());
+        }
+        last_symbol = *b;
+
+        // can use up to 8 * 6 = 48 bits of the u64, if last chunk has no padding.
+        // To minimize shifts, pack the leftovers from left to right.
+        let shift = 64 - (morsels_in_leftover + 1) * 6;
+        // tables are all 256 elements, lookup with a u8 index always succeeds
+        let morsel = decode_table[*b as usize];
+        if morsel == tables::INVALID_VALUE {
+            return Err(invalid_byte_error(start_of_leftovers + i, *b));
+        }
+
+        leftover_bits |= (morsel as u64) << shift;
+        morsels_in_leftover += 1;
+    }
+
+    // escaped padding comes after everything else, so it's only subject to case #2
+    for i in leftovers.len()..(leftovers.len() + escaped_padding) {
+        if i % 4 < 2 {
+            return Err(invalid_padding_error());
+        }
+
+        padding_bytes += 1;
+    }
+
+    // start_of_leftovers is a multiple of 8, so this is also the position within the last quad
+    if config.decode_padding_mode == DecodePaddingMode::RequireCanonical
+        && (morsels_in_leftover + padding_bytes) % 4 != 0
+    {
+        return Err(invalid_padding_error());
+    }
+
+    let leftover_bits_ready_to_append = match morsels_in_leftover {
+        0 => 0,
+        2 => 8,
+        3 => 16,
+        4 => 24,
+        6 => 32,
+        7 => 40,
+        8 => 48,
+        _ => unreachable!(
+            "Impossible: must only have 0 to 8 input bytes in last chunk, with no invalid lengths"
+        ),
+    };
+
+    // if there are bits set outside the bits we care about, last symbol encodes trailing bits that
+    // will not be included in the output
+    let mask = !0 >> leftover_bits_ready_to_append;
+    if !config.decode_allow_trailing_bits && (leftover_bits & mask) != 0 {
+        // last morsel is at `morsels_in_leftover` - 1
+        return Err(invalid_last_symbol_error(
+            start_of_leftovers + morsels_in_leftover - 1,
+            last_symbol,
+        ));
+    }
+
+    if output.len() - output_index < leftover_bits_ready_to_append / 8 {
+        return Err(output_slice_too_small_error());
+    }
+
+    let mut leftover_bits_appended_to_buf = 0;
+    while leftover_bits_appended_to_buf < leftover_bits_ready_to_append {
+        // `as` simply truncates the higher bits, which is what we want here
+        let selected_bits = (leftover_bits >> (56 - leftover_bits_appended_to_buf)) as u8;
+        output[output_index] = selected_bits;
+        output_index += 1;
+
+        leftover_bits_appended_to_buf += 8;
+    }
+
+    Ok(DecodeMetadata {
+        decoded_len: output_index,
+        padding_len: padding_bytes,
+        skipped_len: 0,
+    })
+}
+
+/// Decode without branching on, or indexing by, the value of any symbol, so that any valid input of
+/// a given length takes the same time.
+///
+/// Returns `None` if the input is invalid or doesn't fit in `output`, so that `decode_helper` can
+/// find the exact error. Only then does the input affect what happens.
+fn decode_constant_time(
+    input: &[u8],
+    escaped_padding: usize,
+    config: Config,
+    output: &mut [u8],
+) -> Option<DecodeMetadata> {
+    let encode_table = config.char_set.encode_table();
+    let alias_table = config.char_set.alias_table();
+    let lookup = |b| {
+        let (morsel, found) = lookup_constant_time(b, encode_table);
+        match alias_table {
+            // a symbol in both tables has the same value in each, so the results can be combined
+            Some(alias_table) => {
+                let (alias_morsel, alias_found) = lookup_constant_time(b, alias_table);
+                (morsel | alias_morsel, found | alias_found)
+            }
+            None => (morsel, found),
+        }
     };
 
+    // the amount of padding isn't secret, since it follows from the length of the decoded data
+    let unescaped_padding_len = input
+        .iter()
+        .rev()
+        .take_while(|&&b| b == config.pad_byte)
+        .count();
+    let padding_len = unescaped_padding_len + escaped_padding;
+    let symbols = &input[..(input.len() - unescaped_padding_len)];
+    let symbols_in_last_quad = symbols.len() % 4;
+
+    let decoded_len = symbols.len() / 4 * 3
+        + match symbols_in_last_quad {
+            0 => 0,
+            2 => 1,
+            3 => 2,
+            _ => return None,
+        };
+    // same rules as decode_helper: padding only fills out the last quad, after at least 2 symbols
+    let padding_ok = (padding_len == 0
+        || (symbols_in_last_quad >= 2 && symbols_in_last_quad + padding_len <= 4))
+        && (config.decode_padding_mode != DecodePaddingMode::RequireCanonical
+            || (symbols.len() + padding_len) % 4 == 0);
+    if !padding_ok || output.len() < decoded_len {
+        return None;
+    }
+
+    // nonzero if any symbol isn't in the character set or has trailing bits set
+    let mut invalid = 0_u8;
+    let (quads, leftovers) = symbols.split_at(symbols.len() - symbols_in_last_quad);
+
+    for (quad, decoded) in quads.chunks_exact(4).zip(output.chunks_exact_mut(3)) {
+        let mut quad_bits = 0_u32;
+        for &b in quad {
+            let (morsel, found) = lookup(b);
+            invalid |= !found;
+            quad_bits = (quad_bits << 6) | morsel as u32;
+        }
+
+        decoded.copy_from_slice(&quad_bits.to_be_bytes()[1..]);
+    }
+
+    if !leftovers.is_empty() {
+        let mut quad_bits = 0_u32;
+        for &b in leftovers {
+            let (morsel, found) = lookup(b);
+            invalid |= !found;
+            quad_bits = (quad_bits << 6) | morsel as u32;
+        }
+        quad_bits <<= 6 * (4 - leftovers.len());
+
+        let leftover_decoded_len = leftovers.len() - 1;
+        let trailing_bits = quad_bits & (0xFF_FFFF >> (leftover_decoded_len * 8));
+        if !config.decode_allow_trailing_bits {
+            invalid |= (trailing_bits != 0) as u8;
+        }
+
+        output[(decoded_len - leftover_decoded_len)..decoded_len]
+            .copy_from_slice(&quad_bits.to_be_bytes()[1..(1 + leftover_decoded_len)]);
+    }
+
+    if invalid != 0 {
+        return None;
+    }
+
+    Some(DecodeMetadata {
+        decoded_len,
+        padding_len,
+        skipped_len: 0,
+    })
+}
+
+/// Look up `b` by comparing it with every symbol in `encode_table`. Returns its value and `0xFF` if
+/// it's a symbol, or `0` and `0` if it isn't.
+fn lookup_constant_time(b: u8, encode_table: &[u8; 64]) -> (u8, u8) {
+    let mut morsel = 0;
+    let mut found = 0;
+
+    for (value, &symbol) in encode_table.iter().enumerate() {
+        // 0xFF if b == symbol, otherwise 0
+        let eq = (((b ^ symbol) as u32).wrapping_sub(1) >> 8) as u8;
+        morsel |= eq & value as u8;
+        found |= eq;
+    }
+
+    (morsel, found)
+}
+
+/// Decode a byte at a time, skipping bytes that are neither symbols nor padding. This follows the
+/// same rules as `decode_helper` for padding and trailing bits, and error offsets refer to the
+/// unfiltered input.
+fn decode_ignoring_invalid_bytes(
+    input: &[u8],
+    escaped_padding: usize,
+    config: Config,
+    decode_table: &[u8; 256],
+    output: &mut [u8],
+) -> Result<DecodeMetadata, DecodeError> {
+    let mut output_index = 0;
+
+    let bytes = input
+        .iter()
+        .cloned()
+        .chain(iter::repeat(config.pad_byte).take(escaped_padding));
+    let padding_len = decode_bytewise(bytes, config, decode_table, |decoded| {
+        if output.len() - output_index < decoded.len() {
+            return Err(DecodeError::OutputSliceTooSmall);
+        }
+
+        output[output_index..(output_index + decoded.len())].copy_from_slice(decoded);
+        output_index += decoded.len();
+        Ok(())
+    })?;
+
+    // only counted once the input is known to be valid, so errors don't pay for it
+    let skipped_len = input
+        .iter()
+        .filter(|&&b| b != config.pad_byte && decode_table[b as usize] == tables::INVALID_VALUE)
+        .count();
+
+    Ok(DecodeMetadata {
+        decoded_len: output_index,
+        padding_len,
+        skipped_len,
+    })
+}
+
+/// Decode a byte at a time, passing each group of up to 3 decoded bytes to `write`. Bytes that are
+/// neither symbols nor padding are skipped if `Config::decode_ignore_invalid` is set.
+///
+/// Returns how much padding there was.
+fn decode_bytewise<I, W>(
+    input: I,
+    config: Config,
+    decode_table: &[u8; 256],
+    mut write: W,
+) -> Result<usize, DecodeError>
+where
+    I: Iterator<Item = u8>,
+    W: FnMut(&[u8]) -> Result<(), DecodeError>,
+{
+    let mut decoder = BytewiseDecoder::default();
+    decoder.feed(input, config, decode_table, &mut write)?;
+    decoder.finish(config, write)
+}
+
+/// The state of a byte-at-a-time decode, so that input can be fed to it a piece at a time.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct BytewiseDecoder {
+    // symbols of the current quad, packed into the low bits
+    quad_bits: u32,
+    morsels_in_quad: usize,
+    padding_bytes: usize,
+    last_symbol: (usize, u8),
+    // the first whitespace byte and its offset, if whitespace that may be trailing has been seen
+    trailing_whitespace: Option<(usize, u8)>,
+    // offset of the next byte of input
+    offset: usize,
+}
+
+impl BytewiseDecoder {
+    /// True if no part of a quad, and no padding, has been seen since the last complete quad.
+    #[cfg(any(feature = "alloc", feature = "std", test))]
+    pub(crate) fn is_between_quads(&self) -> bool {
+        self.morsels_in_quad == 0 && self.padding_bytes == 0 && self.trailing_whitespace.is_none()
+    }
+
+    /// The offset of the next byte of input.
+    #[cfg(any(feature = "alloc", feature = "std", test))]
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Account for `len` bytes of input that were decoded some other way, between quads.
+    #[cfg(any(feature = "alloc", feature = "std", test))]
+    pub(crate) fn skip(&mut self, len: usize) {
+        debug_assert!(self.is_between_quads());
+        self.offset += len;
+    }
+
+    /// Decode `input`, passing each complete group of 3 decoded bytes to `write`. Error offsets
+    /// count all input fed so far.
+    pub(crate) fn feed<I, W>(
+        &mut self,
+        input: I,
+        config: Config,
+        decode_table: &[u8; 256],
+        mut write: W,
+    ) -> Result<(), DecodeError>
+    where
+        I: Iterator<Item = u8>,
+        W: FnMut(&[u8]) -> Result<(), DecodeError>,
+    {
+        for b in input {
+            let i = self.offset;
+            self.offset += 1;
+
+            if let Some((index, byte)) = self.trailing_whitespace {
+                // the whitespace wasn't trailing after all
+                if !b.is_ascii_whitespace() || b == config.pad_byte {
+                    return Err(DecodeError::InvalidByte(index, byte));
+                }
+
+                continue;
+            }
+
+            if b == config.pad_byte {
+                // padding can only fill the last 1 or 2 positions of a quad, unless there's no limit
+                // on trailing padding
+                if config.decode_padding_mode != DecodePaddingMode::IgnoreTrailing
+                    && (self.morsels_in_quad + self.padding_bytes) % 4 < 2
+                {
+                    return Err(DecodeError::InvalidPadding);
+                }
+
+                self.padding_bytes += 1;
+                continue;
+            }
+
+            let morsel = decode_table[b as usize];
+            if morsel == tables::INVALID_VALUE {
+                if config.decode_ignore_invalid {
+                    continue;
+                }
+
+                if config.decode_allow_trailing_whitespace && b.is_ascii_whitespace() {
+                    self.trailing_whitespace = Some((i, b));
+                    continue;
+                }
+
+                return Err(DecodeError::InvalidByte(i, b));
+            }
+
+            if self.padding_bytes > 0 {
+                return Err(DecodeError::InvalidPadding);
+            }
+
+            self.quad_bits = (self.quad_bits << 6) | morsel as u32;
+            self.morsels_in_quad += 1;
+            self.last_symbol = (i, b);
+
+            if self.morsels_in_quad == 4 {
+                write(&self.quad_bits.to_be_bytes()[1..])?;
+                self.quad_bits = 0;
+                self.morsels_in_quad = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the input ended properly, and pass any decoded bytes left in an incomplete quad
+    /// to `write`. Returns how much padding there was.
+    pub(crate) fn finish<W>(self, config: Config, mut write: W) -> Result<usize, DecodeError>
+    where
+        W: FnMut(&[u8]) -> Result<(), DecodeError>,
+    {
+        if config.decode_padding_mode == DecodePaddingMode::RequireCanonical
+            && (self.morsels_in_quad + self.padding_bytes) % 4 != 0
+        {
+            return Err(DecodeError::InvalidPadding);
+        }
+
+        let leftover_bytes = match self.morsels_in_quad {
+            0 => return Ok(0),
+            1 => return Err(DecodeError::InvalidLength),
+            n => n - 1,
+        };
+
+        // 2 symbols have 4 bits more than 1 byte needs, 3 symbols have 2 bits more than 2 bytes
+        let excess_bits = self.morsels_in_quad * 6 - leftover_bytes * 8;
+        if !config.decode_allow_trailing_bits && self.quad_bits & ((1 << excess_bits) - 1) != 0 {
+            let (index, symbol) = self.last_symbol;
+            return Err(DecodeError::InvalidLastSymbol(index, symbol));
+        }
+
+        let leftover = (self.quad_bits >> excess_bits).to_be_bytes();
+        write(&leftover[(4 - leftover_bytes)..])?;
+
+        Ok(self.padding_bytes)
+    }
+}
+
+// Big-endian, so the bits decoded first end up in the first byte
+#[inline]
+fn write_u64(output: &mut [u8], value: u64) {
+    output[..8].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Decode 8 bytes of input into 6 bytes of output. 8 bytes of output will be written, but only the
+/// first 6 of those contain meaningful data.
+///
+/// `input` is the bytes to decode, of which the first 8 bytes will be processed.
+/// `index_at_start_of_input` is the offset in the overall input (used for reporting errors
+/// accurately)
+/// `decode_table` is the lookup table for the particular base64 alphabet.
+/// `output` will have its first 8 bytes overwritten, of which only the first 6 are valid decoded
+/// data.
+// yes, really inline (worth 30-50% speedup)
+#[inline(always)]
+fn decode_chunk(
+    input: &[u8],
+    index_at_start_of_input: usize,
+    decode_table: &[u8; 256],
+    output: &mut [u8],
+) -> Result<(), DecodeError> {
+    let mut accum: u64;
+
+    let morsel = decode_table[input[0] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input, input[0]));
+    }
+    accum = (morsel as u64) << 58;
+
+    let morsel = decode_table[input[1] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 1, input[1]));
+    }
+    accum |= (morsel as u64) << 52;
+
+    let morsel = decode_table[input[2] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 2, input[2]));
+    }
+    accum |= (morsel as u64) << 46;
+
+    let morsel = decode_table[input[3] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 3, input[3]));
+    }
+    accum |= (morsel as u64) << 40;
+
+    let morsel = decode_table[input[4] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 4, input[4]));
+    }
+    accum |= (morsel as u64) << 34;
+
+    let morsel = decode_table[input[5] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 5, input[5]));
+    }
+    accum |= (morsel as u64) << 28;
+
+    let morsel = decode_table[input[6] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 6, input[6]));
+    }
+    accum |= (morsel as u64) << 22;
+
+    let morsel = decode_table[input[7] as usize];
+    if morsel == tables::INVALID_VALUE {
+        return Err(invalid_byte_error(index_at_start_of_input + 7, input[7]));
+    }
+    accum |= (morsel as u64) << 16;
+
+    write_u64(output, accum);
+
+    Ok(())
+}
+
+/// Decode an 8-byte chunk, but only write the 6 bytes actually decoded instead of including 2
+/// trailing garbage bytes.
+#[inline]
+fn decode_chunk_precise(
+    input: &[u8],
+    index_at_start_of_input: usize,
+    decode_table: &[u8; 256],
+    output: &mut [u8],
+) -> Result<(), DecodeError> {
+    let mut tmp_buf = [0_u8; 8];
+
+    decode_chunk(
+        input,
+        index_at_start_of_input,
+        decode_table,
+        &mut tmp_buf[..],
+    )?;
+
+    output[0..6].copy_from_slice(&tmp_buf[0..6]);
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::needless_borrows_for_generic_args, clippy::vec_init_then_push)]
+mod tests {
+    use super::*;
+    use crate::{
+        encode::{encode_config, encode_config_buf, encode_config_slice, encoded_len},
+        tests::{assert_encode_sanity, random_config},
+        PAD_BYTE,
+    };
+
+    use rand::{
+        distributions::{Distribution, Uniform},
+        seq::SliceRandom,
+        FromEntropy, Rng,
+    };
+
+    #[test]
+    fn decode_chunk_precise_writes_only_6_bytes() {
+        let input = b"Zm9vYmFy"; // "foobar"
+        let mut output = [0_u8, 1, 2, 3, 4, 5, 6, 7];
+        decode_chunk_precise(&input[..], 0, tables::STANDARD_DECODE, &mut output).unwrap();
+        assert_eq!(&vec![b'f', b'o', b'o', b'b', b'a', b'r', 6, 7], &output);
+    }
+
+    #[test]
+    fn decode_chunk_writes_8_bytes() {
+        let input = b"Zm9vYmFy"; // "foobar"
+        let mut output = [0_u8, 1, 2, 3, 4, 5, 6, 7];
+        decode_chunk(&input[..], 0, tables::STANDARD_DECODE, &mut output).unwrap();
+        assert_eq!(&vec![b'f', b'o', b'o', b'b', b'a', b'r', 0, 0], &output);
+    }
+
+    #[test]
+    fn decode_into_nonempty_vec_doesnt_clobber_existing_prefix() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decoded_with_prefix = Vec::new();
+        let mut decoded_without_prefix = Vec::new();
+        let mut prefix = Vec::new();
+
+        let prefix_len_range = Uniform::new(0, 1000);
+        let input_len_range = Uniform::new(0, 1000);
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+            decoded_with_prefix.clear();
+            decoded_without_prefix.clear();
+            prefix.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            assert_encode_sanity(&encoded_data, config, input_len);
+
+            let prefix_len = prefix_len_range.sample(&mut rng);
+
+            // fill the buf with a prefix
+            for _ in 0..prefix_len {
+                prefix.push(rng.gen());
+            }
+
+            decoded_with_prefix.resize(prefix_len, 0);
+            decoded_with_prefix.copy_from_slice(&prefix);
+
+            // decode into the non-empty buf
+            decode_config_buf(&encoded_data, config, &mut decoded_with_prefix).unwrap();
+            // also decode into the empty buf
+            decode_config_buf(&encoded_data, config, &mut decoded_without_prefix).unwrap();
+
+            assert_eq!(
+                prefix_len + decoded_without_prefix.len(),
+                decoded_with_prefix.len()
+            );
+            assert_eq!(orig_data, decoded_without_prefix);
+
+            // append plain decode onto prefix
+            prefix.append(&mut decoded_without_prefix);
+
+            assert_eq!(prefix, decoded_with_prefix);
+        }
+    }
+
+    #[test]
+    fn decode_into_slice_doesnt_clobber_existing_prefix_or_suffix() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decode_buf = Vec::new();
+        let mut decode_buf_copy: Vec<u8> = Vec::new();
+
+        let input_len_range = Uniform::new(0, 1000);
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+            decode_buf.clear();
+            decode_buf_copy.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            assert_encode_sanity(&encoded_data, config, input_len);
+
+            // fill the buffer with random garbage, long enough to have some room before and after
+            for _ in 0..5000 {
+                decode_buf.push(rng.gen());
+            }
+
+            // keep a copy for later comparison
+            decode_buf_copy.extend(decode_buf.iter());
+
+            let offset = 1000;
+
+            // decode into the non-empty buf
+            let decode_bytes_written =
+                decode_config_slice(&encoded_data, config, &mut decode_buf[offset..]).unwrap();
+
+            assert_eq!(orig_data.len(), decode_bytes_written);
+            assert_eq!(
+                orig_data,
+                &decode_buf[offset..(offset + decode_bytes_written)]
+            );
+            assert_eq!(&decode_buf_copy[0..offset], &decode_buf[0..offset]);
+            assert_eq!(
+                &decode_buf_copy[offset + decode_bytes_written..],
+                &decode_buf[offset + decode_bytes_written..]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_into_slice_fits_in_precisely_sized_slice() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decode_buf = Vec::new();
+
+        let input_len_range = Uniform::new(0, 1000);
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+            decode_buf.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            assert_encode_sanity(&encoded_data, config, input_len);
+
+            decode_buf.resize(input_len, 0);
+
+            // decode into the non-empty buf
+            let decode_bytes_written =
+                decode_config_slice(&encoded_data, config, &mut decode_buf[..]).unwrap();
+
+            assert_eq!(orig_data.len(), decode_bytes_written);
+            assert_eq!(orig_data, decode_buf);
+        }
+    }
+
+    #[test]
+    fn decode_into_slice_too_small_returns_error() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decode_buf = Vec::new();
+
+        let input_len_range = Uniform::new(1, 1000);
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+            decode_buf.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            assert_encode_sanity(&encoded_data, config, input_len);
+
+            // anything shorter than the decoded data won't fit
+            let short_len = rng.gen_range(0, input_len);
+            decode_buf.resize(short_len, 0);
+
+            assert_eq!(
+                Err(DecodeError::OutputSliceTooSmall),
+                decode_config_slice(&encoded_data, config, &mut decode_buf[..])
+            );
+        }
+    }
+
+    #[test]
+    fn max_decoded_len_fits_decoded_data() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decode_buf = Vec::new();
+
+        let input_len_range = Uniform::new(0, 1000);
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+            decode_buf.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+
+            let max_len = max_decoded_len(encoded_data.len());
+            assert!(max_len >= input_len);
+            if !config.pad {
+                assert_eq!(input_len, max_len);
+            }
+
+            decode_buf.resize(max_len, 0);
+            assert_eq!(
+                input_len,
+                decode_config_slice(&encoded_data, config, &mut decode_buf[..]).unwrap()
+            );
+            assert_eq!(orig_data, &decode_buf[..input_len]);
+        }
+    }
+
+    #[test]
+    fn max_decoded_len_doesnt_overflow() {
+        assert_eq!(
+            usize::max_value() / 4 * 3 + 2,
+            max_decoded_len(usize::max_value())
+        );
+    }
+
+    #[test]
+    fn detect_invalid_last_symbol_two_bytes() {
+        let decode =
+            |input, forgiving| decode_config(input, STANDARD.decode_allow_trailing_bits(forgiving));
+
+        // example from https://github.com/marshallpierce/rust-base64/issues/75
+        assert!(decode("iYU=", false).is_ok());
+        // trailing 01
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(2, b'V')),
+            decode("iYV=", false)
+        );
+        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
+        // trailing 10
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(2, b'W')),
+            decode("iYW=", false)
+        );
+        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
+        // trailing 11
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(2, b'X')),
+            decode("iYX=", false)
+        );
+        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
+
+        // also works when there are 2 quads in the last block
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(6, b'X')),
+            decode("AAAAiYX=", false)
+        );
+        assert_eq!(Ok(vec![0, 0, 0, 137, 133]), decode("AAAAiYX=", true));
+    }
+
+    #[test]
+    fn detect_invalid_last_symbol_one_byte() {
+        // 0xFF -> "/w==", so all letters > w, 0-9, and '+', '/' should get InvalidLastSymbol
+
+        assert!(decode("/w==").is_ok());
+        // trailing 01
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'x')), decode("/x=="));
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'z')), decode("/z=="));
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'0')), decode("/0=="));
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'9')), decode("/9=="));
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'+')), decode("/+=="));
+        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'/')), decode("//=="));
+
+        // also works when there are 2 quads in the last block
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(5, b'x')),
+            decode("AAAA/x==")
+        );
+    }
+
+    #[test]
+    fn detect_invalid_last_symbol_every_possible_three_symbols() {
+        let mut base64_to_bytes = ::std::collections::HashMap::new();
+
+        let mut bytes = [0_u8; 2];
+        for b1 in 0_u16..256 {
+            bytes[0] = b1 as u8;
+            for b2 in 0_u16..256 {
+                bytes[1] = b2 as u8;
+                let mut b64 = vec![0_u8; 4];
+                assert_eq!(4, encode_config_slice(&bytes, STANDARD, &mut b64[..]));
+                let mut v = ::std::vec::Vec::with_capacity(2);
+                v.extend_from_slice(&bytes[..]);
+
+                assert!(base64_to_bytes.insert(b64, v).is_none());
+            }
+        }
+
+        // every possible combination of symbols must either decode to 2 bytes or get InvalidLastSymbol
+
+        let mut symbols = [0_u8; 4];
+        for &s1 in STANDARD.char_set.encode_table().iter() {
+            symbols[0] = s1;
+            for &s2 in STANDARD.char_set.encode_table().iter() {
+                symbols[1] = s2;
+                for &s3 in STANDARD.char_set.encode_table().iter() {
+                    symbols[2] = s3;
+                    symbols[3] = PAD_BYTE;
+
+                    match base64_to_bytes.get(&symbols[..]) {
+                        Some(bytes) => {
+                            assert_eq!(Ok(bytes.to_vec()), decode_config(&symbols, STANDARD))
+                        }
+                        None => assert_eq!(
+                            Err(DecodeError::InvalidLastSymbol(2, s3)),
+                            decode_config(&symbols[..], STANDARD)
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn detect_invalid_last_symbol_every_possible_two_symbols() {
+        let mut base64_to_bytes = ::std::collections::HashMap::new();
+
+        for b in 0_u16..256 {
+            let mut b64 = vec![0_u8; 4];
+            assert_eq!(4, encode_config_slice(&[b as u8], STANDARD, &mut b64[..]));
+            let mut v = ::std::vec::Vec::with_capacity(1);
+            v.push(b as u8);
+
+            assert!(base64_to_bytes.insert(b64, v).is_none());
+        }
+
+        // every possible combination of symbols must either decode to 1 byte or get InvalidLastSymbol
+
+        let mut symbols = [0_u8; 4];
+        for &s1 in STANDARD.char_set.encode_table().iter() {
+            symbols[0] = s1;
+            for &s2 in STANDARD.char_set.encode_table().iter() {
+                symbols[1] = s2;
+                symbols[2] = PAD_BYTE;
+                symbols[3] = PAD_BYTE;
+
+                match base64_to_bytes.get(&symbols[..]) {
+                    Some(bytes) => {
+                        assert_eq!(Ok(bytes.to_vec()), decode_config(&symbols, STANDARD))
+                    }
+                    None => assert_eq!(
+                        Err(DecodeError::InvalidLastSymbol(1, s2)),
+                        decode_config(&symbols[..], STANDARD)
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_ignore_invalid_skips_junk() {
+        let config = STANDARD.decode_ignore_invalid(true);
+
+        assert_eq!(
+            Ok(b"abcd".to_vec()),
+            decode_config("YW\r\nJj ZA==!", config)
+        );
+        assert_eq!(Ok(b"abc".to_vec()), decode_config("-Y-W-J-j-", config));
+        assert_eq!(Ok(Vec::new()), decode_config("\n\t!", config));
+        // without the flag, junk is still an error
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'\r')),
+            decode_config("YW\r\nJj ZA==", STANDARD)
+        );
+    }
+
+    #[test]
+    fn decode_ignore_invalid_errors_refer_to_original_offsets() {
+        let config = STANDARD.decode_ignore_invalid(true);
+
+        // padding followed by a symbol, and other padding errors, don't have offsets
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("Y W=Jj", config)
+        );
+        // padding after only one symbol in a quad
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("Y =", config)
+        );
+        // three padding bytes
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("YW =\n==", config)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(2, b'R')),
+            decode_config("Y R==", config)
+        );
+        assert_eq!(
+            Ok(b"a".to_vec()),
+            decode_config("Y R==", config.decode_allow_trailing_bits(true))
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidLength),
+            decode_config("YWJj Z", config)
+        );
+    }
+
     #[test]
-    fn decode_chunk_precise_writes_only_6_bytes() {
-        let input = b"Zm9vYmFy"; // "foobar"
-        let mut output = [0_u8, 1, 2, 3, 4, 5, 6, 7];
-        decode_chunk_precise(&input[..], 0, tables::STANDARD_DECODE, &mut output).unwrap();
-        assert_eq!(&vec![b'f', b'o', b'o', b'b', b'a', b'r', 6, 7], &output);
+    fn decode_ignore_invalid_padding_split_by_line_breaks() {
+        let config = STANDARD.decode_ignore_invalid(true);
+
+        assert_eq!(
+            Ok(b"any carna pleasure".to_vec()),
+            decode_config("YW55\r\nIGNhcm5h\r\nIHBsZWFzdXJl\r\n", config)
+        );
+
+        // padding position is counted among symbols only, not line breaks
+        for &input in &[
+            "YW55IGNhcm5hbCBwbGVhcw\r\n==",
+            "YW55IGNhcm5hbCBwbGVhcw=\r\n=",
+            "YW55IGNhcm5hbCBwbGVhcw=\r\n=\r\n",
+            "YW55\r\nIGNhcm5h\r\nbCBwbGVh\r\ncw==",
+        ] {
+            assert_eq!(
+                Ok(b"any carnal pleas".to_vec()),
+                decode_config(input, config)
+            );
+            assert_eq!(
+                Ok(b"any carnal pleas".to_vec()),
+                decode_config(
+                    input,
+                    config.decode_padding_mode(DecodePaddingMode::RequireCanonical)
+                )
+            );
+        }
+        assert_eq!(
+            Ok(b"any carnal pleasu".to_vec()),
+            decode_config("YW55IGNhcm5hbCBwbGVhc3\r\nU=", config)
+        );
+
+        // a third padding byte is still too many, wherever the line breaks fall
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("YW55IGNhcm5hbCBwbGVhcw=\r\n==", config)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("YW55IGNhcm5hbCBwbGVhc3U=\r\n=", config)
+        );
     }
 
     #[test]
-    fn decode_chunk_writes_8_bytes() {
-        let input = b"Zm9vYmFy"; // "foobar"
-        let mut output = [0_u8, 1, 2, 3, 4, 5, 6, 7];
-        decode_chunk(&input[..], 0, tables::STANDARD_DECODE, &mut output).unwrap();
-        assert_eq!(&vec![b'f', b'o', b'o', b'b', b'a', b'r', 0, 0], &output);
+    fn decode_ignore_invalid_whitespace_around_padding() {
+        type Decode = fn(&str, Config) -> Result<Vec<u8>, DecodeError>;
+
+        let config = STANDARD.decode_ignore_invalid(true);
+        let decoders: [Decode; 5] = [
+            |input, config| decode_config(input, config),
+            |input, config| {
+                let mut output = [0_u8; 8];
+                decode_config_slice(input, config, &mut output).map(|len| output[..len].to_vec())
+            },
+            |input, config| {
+                let mut buf = input.as_bytes().to_vec();
+                decode_in_place(&mut buf, config).map(|len| buf[..len].to_vec())
+            },
+            |input, config| decode_iter(input.bytes(), config),
+            |input, config| {
+                let mut decoder = crate::StreamDecoder::new(config);
+                let mut output = decoder.feed(input.as_bytes())?;
+                output.extend(decoder.finish()?);
+                Ok(output)
+            },
+        ];
+
+        for decode in decoders.iter() {
+            // whitespace between the padding bytes, or between the symbols and the padding
+            for &(input, expected) in &[
+                ("QQ= =\n", &b"A"[..]),
+                ("QQ\n==", b"A"),
+                ("QQ\r\n=\r\n=\r\n", b"A"),
+                ("Q Q==", b"A"),
+                ("QUI\n=\n", b"AB"),
+            ] {
+                assert_eq!(Ok(expected.to_vec()), decode(input, config), "{:?}", input);
+            }
+
+            // padding is only allowed once 2 symbols of the quad have been seen, whitespace aside
+            for &input in &["Q\n===", "Q =Q=", "\n==QQ", "QQ=\n==", "QUI= ="] {
+                assert_eq!(
+                    Err(DecodeError::InvalidPadding),
+                    decode(input, config),
+                    "{:?}",
+                    input
+                );
+            }
+        }
     }
 
     #[test]
-    fn decode_into_nonempty_vec_doesnt_clobber_existing_prefix() {
+    fn decode_ignore_invalid_with_junk_matches_clean_decode() {
         let mut orig_data = Vec::new();
         let mut encoded_data = String::new();
-        let mut decoded_with_prefix = Vec::new();
-        let mut decoded_without_prefix = Vec::new();
-        let mut prefix = Vec::new();
+        let mut noisy_data = Vec::new();
+        let mut decode_buf = Vec::new();
 
-        let prefix_len_range = Uniform::new(0, 1000);
         let input_len_range = Uniform::new(0, 1000);
 
         let mut rng = rand::rngs::SmallRng::from_entropy();
 
-        for _ in 0..10_000 {
+        for _ in 0..1_000 {
             orig_data.clear();
             encoded_data.clear();
-            decoded_with_prefix.clear();
-            decoded_without_prefix.clear();
-            prefix.clear();
+            noisy_data.clear();
 
             let input_len = input_len_range.sample(&mut rng);
 
@@ -616,258 +2353,925 @@ mod tests {
                 orig_data.push(rng.gen());
             }
 
-            let config = random_config(&mut rng);
+            let config = random_config(&mut rng).decode_ignore_invalid(true);
             encode_config_buf(&orig_data, config, &mut encoded_data);
             assert_encode_sanity(&encoded_data, config, input_len);
 
-            let prefix_len = prefix_len_range.sample(&mut rng);
+            let mut custom_decode_table = None;
+            let decode_table = config.char_set.decode_table(&mut custom_decode_table);
+            let junk: Vec<u8> = (0_u8..=255)
+                .filter(|&b| {
+                    b != config.pad_byte && decode_table[b as usize] == tables::INVALID_VALUE
+                })
+                .collect();
+
+            for &b in encoded_data.as_bytes() {
+                while rng.gen_bool(0.1) {
+                    noisy_data.push(*junk.choose(&mut rng).unwrap());
+                }
+                noisy_data.push(b);
+            }
 
-            // fill the buf with a prefix
-            for _ in 0..prefix_len {
-                prefix.push(rng.gen());
+            assert_eq!(orig_data, decode_config(&noisy_data, config).unwrap());
+
+            decode_buf.resize(input_len, 0);
+            assert_eq!(
+                Ok(input_len),
+                decode_config_slice(&noisy_data, config, &mut decode_buf[..])
+            );
+            assert_eq!(orig_data, decode_buf);
+
+            if input_len > 0 {
+                decode_buf.truncate(input_len - 1);
+                assert_eq!(
+                    Err(DecodeError::OutputSliceTooSmall),
+                    decode_config_slice(&noisy_data, config, &mut decode_buf[..])
+                );
             }
+        }
+    }
 
-            decoded_with_prefix.resize(prefix_len, 0);
-            decoded_with_prefix.copy_from_slice(&prefix);
+    #[test]
+    fn decode_constant_time_decodes_valid_input_itself() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let mut decode_buf = Vec::new();
+        let input_len_range = Uniform::new(0, 1000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
 
-            // decode into the non-empty buf
-            decode_config_buf(&encoded_data, config, &mut decoded_with_prefix).unwrap();
-            // also decode into the empty buf
-            decode_config_buf(&encoded_data, config, &mut decoded_without_prefix).unwrap();
+        for _ in 0..1_000 {
+            orig_data.clear();
+            encoded_data.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+
+            // exactly sized, so any extra bytes written would panic
+            decode_buf.clear();
+            decode_buf.resize(input_len, 0);
+            let metadata =
+                decode_constant_time(encoded_data.as_bytes(), 0, config, &mut decode_buf).unwrap();
+            assert_eq!(input_len, metadata.decoded_len());
+            assert_eq!(orig_data, decode_buf);
+        }
+
+        assert_eq!(
+            None,
+            decode_constant_time(b"YWJj*A==", 0, STANDARD, &mut [0; 4])
+        );
+        assert_eq!(
+            None,
+            decode_constant_time(b"YR==", 0, STANDARD, &mut [0; 4])
+        );
+        assert_eq!(
+            None,
+            decode_constant_time(b"YWJj", 0, STANDARD, &mut [0; 2])
+        );
+    }
+
+    #[test]
+    fn decode_constant_time_matches_usual_decode() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = Vec::new();
+        let mut decode_buf = Vec::new();
+        let mut constant_time_decode_buf = Vec::new();
+        let input_len_range = Uniform::new(0, 100);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        let padding_modes = [
+            DecodePaddingMode::Indifferent,
+            DecodePaddingMode::RequireCanonical,
+            DecodePaddingMode::IgnoreTrailing,
+        ];
+
+        for _ in 0..100_000 {
+            orig_data.clear();
+            encoded_data.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng)
+                .decode_allow_trailing_bits(rng.gen())
+                .decode_padding_mode(*padding_modes.choose(&mut rng).unwrap());
+            encoded_data.resize(encoded_len(input_len, config).unwrap(), 0);
+            let _ = encode_config_slice(&orig_data, config, &mut encoded_data);
+
+            // mess up the input some of the time
+            if !encoded_data.is_empty() && rng.gen_bool(0.5) {
+                let index = rng.gen_range(0, encoded_data.len());
+                match rng.gen_range(0, 3) {
+                    0 => encoded_data[index] = rng.gen(),
+                    1 => encoded_data.insert(index, config.pad_byte),
+                    _ => {
+                        let _ = encoded_data.remove(index);
+                    }
+                }
+            }
+
+            let constant_time_config = config.decode_constant_time(true);
+            assert_eq!(
+                decode_config_with_info(&encoded_data, config),
+                decode_config_with_info(&encoded_data, constant_time_config)
+            );
+
+            let output_len = rng.gen_range(0, max_decoded_len(encoded_data.len()) + 2);
+            decode_buf.clear();
+            decode_buf.resize(output_len, 0);
+            constant_time_decode_buf.clear();
+            constant_time_decode_buf.resize(output_len, 0);
+
+            let result = decode_config_slice(&encoded_data, config, &mut decode_buf);
+            assert_eq!(
+                result,
+                decode_config_slice(
+                    &encoded_data,
+                    constant_time_config,
+                    &mut constant_time_decode_buf
+                )
+            );
+            if let Ok(decoded_len) = result {
+                assert_eq!(
+                    &decode_buf[..decoded_len],
+                    &constant_time_decode_buf[..decoded_len]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_iter_matches_decode_config() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let input_len_range = Uniform::new(0, 1000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng).decode_ignore_invalid(rng.gen());
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            if config.decode_ignore_invalid && !encoded_data.is_empty() {
+                let index = rng.gen_range(0, encoded_data.len());
+                encoded_data.insert(index, '\n');
+            }
+
+            assert_eq!(
+                decode_config(&encoded_data, config),
+                decode_iter(encoded_data.bytes(), config)
+            );
+            assert_eq!(
+                orig_data,
+                decode_iter(encoded_data.bytes(), config).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn decode_iter_errors() {
+        let iter = |s: &'static str| s.bytes().filter(|_| true);
+
+        assert_eq!(
+            Err(DecodeError::InvalidByte(5, b'*')),
+            decode_iter(iter("YWJjZ*=="), STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidLength),
+            decode_iter(iter("YWJjZ"), STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(5, b'R')),
+            decode_iter(iter("YWJjZR=="), STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_iter(iter("YW==Jj"), STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_iter(
+                iter("YWI"),
+                STANDARD.decode_padding_mode(DecodePaddingMode::RequireCanonical)
+            )
+        );
+        assert_eq!(
+            Ok(b"ab".to_vec()),
+            decode_iter(
+                iter("YWI===="),
+                STANDARD.decode_padding_mode(DecodePaddingMode::IgnoreTrailing)
+            )
+        );
+        assert_eq!(
+            Ok(b"abcd".to_vec()),
+            decode_iter(iter("YWJjZA=="), STANDARD.decode_max_len(Some(4)))
+        );
+        assert_eq!(
+            Err(DecodeError::TooLong),
+            decode_iter(iter("YWJjZA=="), STANDARD.decode_max_len(Some(3)))
+        );
+    }
+
+    #[test]
+    fn decode_invalid_length_doesnt_allocate() {
+        let mut buf = Vec::new();
+
+        assert_eq!(
+            Err(DecodeError::InvalidLength),
+            decode_config_buf("Y", STANDARD, &mut buf)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidLength),
+            decode_config_buf("YWJj".repeat(1000) + "Y", STANDARD, &mut buf)
+        );
+        // trailing junk is still reported as such
+        assert_eq!(
+            Err(DecodeError::InvalidByte(4000, b'\n')),
+            decode_config_buf("YWJj".repeat(1000) + "\n", STANDARD, &mut buf)
+        );
+        assert_eq!(0, buf.capacity());
+
+        // skipped bytes don't count toward the length
+        assert_eq!(
+            Ok(()),
+            decode_config_buf("YWJj\n", STANDARD.decode_ignore_invalid(true), &mut buf)
+        );
+        assert_eq!(b"abc", &buf[..]);
+    }
+
+    #[test]
+    fn decode_config_buf_error_restores_buffer() {
+        let mut buf = b"prefix".to_vec();
+
+        // errors at the start, in the middle of the fast loop, and at the end
+        let long = "YWJj".repeat(100) + "Y*Jj" + &"YWJj".repeat(100);
+        for &input in &["*WJj", &long, "YWJjZR=="] {
+            assert!(decode_config_buf(input, STANDARD, &mut buf).is_err());
+            assert_eq!(b"prefix", &buf[..]);
+        }
+        assert_eq!(
+            Err(DecodeError::TooLong),
+            decode_config_buf("YWJjZGVm", STANDARD.decode_max_len(Some(4)), &mut buf)
+        );
+        assert_eq!(b"prefix", &buf[..]);
+
+        assert_eq!(Ok(()), decode_config_buf("YWJj", STANDARD, &mut buf));
+        assert_eq!(b"prefixabc", &buf[..]);
+    }
+
+    #[test]
+    fn decode_config_buf_keeps_capacity_across_reuse() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+        let mut buf = Vec::new();
+        let mut capacity = 0;
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            let data: Vec<u8> = (0..rng.gen_range(0, 1_000)).map(|_| rng.gen()).collect();
+            let mut encoded = encode_config(&data, config).into_bytes();
+            // failing shouldn't give any capacity back either
+            let corrupt = rng.gen_bool(0.1);
+            if corrupt {
+                let index = rng.gen_range(0, encoded.len() + 1);
+                encoded.insert(index, b'*');
+            }
+
+            buf.clear();
+            let result = decode_config_buf(&encoded, config, &mut buf);
+            if !corrupt {
+                assert_eq!(Ok(()), result);
+                assert_eq!(data, buf);
+            }
+
+            assert!(buf.capacity() >= capacity);
+            capacity = buf.capacity();
+        }
+
+        // once it's big enough, more inputs of the same size don't make it grow
+        let encoded = encode_config([0_u8; 1_000], STANDARD);
+        buf.clear();
+        decode_config_buf(&encoded, STANDARD, &mut buf).unwrap();
+        let capacity = buf.capacity();
+        for _ in 0..10 {
+            buf.clear();
+            decode_config_buf(&encoded, STANDARD, &mut buf).unwrap();
+            assert_eq!(capacity, buf.capacity());
+        }
+    }
+
+    #[test]
+    fn decoder_reuses_buffer() {
+        let mut decoder = Decoder::new(STANDARD);
+
+        assert_eq!(Ok(&b"hello world"[..]), decoder.decode("aGVsbG8gd29ybGQ="));
+        let capacity = decoder.buf.capacity();
+
+        // shorter output leaves nothing from the previous call behind
+        assert_eq!(Ok(&b"f"[..]), decoder.decode("Zg=="));
+        assert_eq!(capacity, decoder.buf.capacity());
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'*')),
+            decoder.decode("Zm*v")
+        );
+        assert_eq!(capacity, decoder.buf.capacity());
+        assert_eq!(Ok(&b""[..]), decoder.decode(""));
+
+        decoder.reset();
+        assert_eq!(0, decoder.buf.capacity());
+        assert_eq!(Ok(&b"fo"[..]), decoder.decode("Zm8="));
+    }
+
+    #[test]
+    fn decode_config_buf_into_custom_buffer_matches_vec() {
+        // a fixed-capacity buffer that relies on the default `extend_zeroed`
+        struct ArrayBuf {
+            bytes: [u8; 64],
+            len: usize,
+        }
+
+        impl DecodeBuffer for ArrayBuf {
+            fn as_slice(&self) -> &[u8] {
+                &self.bytes[..self.len]
+            }
+
+            fn as_mut_slice(&mut self) -> &mut [u8] {
+                &mut self.bytes[..self.len]
+            }
+
+            fn reserve(&mut self, additional: usize) {
+                assert!(additional <= self.bytes.len() - self.len);
+            }
+
+            fn push(&mut self, byte: u8) {
+                self.bytes[self.len] = byte;
+                self.len += 1;
+            }
+
+            fn truncate(&mut self, len: usize) {
+                self.len = cmp::min(self.len, len);
+            }
+        }
+
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+        let mut encoded = String::new();
+
+        for _ in 0..10_000 {
+            encoded.clear();
+            let config = random_config(&mut rng);
+            let data: Vec<u8> = (0..rng.gen_range(0, 30)).map(|_| rng.gen()).collect();
+            encode_config_buf(&data, config, &mut encoded);
+            let mut encoded_bytes = encoded.clone().into_bytes();
+            if rng.gen() {
+                let index = rng.gen_range(0, encoded_bytes.len() + 1);
+                encoded_bytes.insert(index, b'*');
+            }
+
+            let mut vec = b"prefix".to_vec();
+            let mut array_buf = ArrayBuf {
+                bytes: [0; 64],
+                len: 6,
+            };
+            array_buf.bytes[..6].copy_from_slice(b"prefix");
+
+            assert_eq!(
+                decode_config_buf(&encoded_bytes, config, &mut vec),
+                decode_config_buf(&encoded_bytes, config, &mut array_buf)
+            );
+            assert_eq!(&vec[..], array_buf.as_slice());
+        }
+    }
+
+    #[test]
+    fn decode_exact_checks_length() {
+        let config = STANDARD.pad(false);
+
+        assert_eq!(
+            Ok(b"hello".to_vec()),
+            decode_config_exact("aGVsbG8", config, 5)
+        );
+        assert_eq!(Ok(Vec::new()), decode_config_exact("", config, 0));
+        // shorter than expected, including by a whole quad
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_config_exact("aGVs", config, 5)
+        );
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_config_exact("aGVsbG8", config, 6)
+        );
+        // longer than expected
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_config_exact("aGVsbG8", config, 4)
+        );
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_config_exact("aGVsbG8", config.decode_max_len(Some(4)), 4)
+        );
+        // other errors are passed along
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'*')),
+            decode_config_exact("aG*sbG8", config, 5)
+        );
+        // the config's own limit still applies
+        assert_eq!(
+            Err(DecodeError::TooLong),
+            decode_config_exact("aGVsbG8", config.decode_max_len(Some(3)), 5)
+        );
+    }
+
+    #[test]
+    fn decode_with_info_reports_padding() {
+        for &(input, padding_len) in &[("", 0), ("YQ==", 2), ("YWI=", 1), ("YWJj", 0), ("YQ", 0)] {
+            let (bytes, metadata) = decode_config_with_info(input, STANDARD).unwrap();
+            assert_eq!(decode_config(input, STANDARD).unwrap(), bytes);
+            assert_eq!(bytes.len(), metadata.decoded_len());
+            assert_eq!(padding_len, metadata.padding_len());
+            assert_eq!(padding_len > 0, metadata.is_padded());
+
+            let (lenient_bytes, lenient_metadata) =
+                decode_config_with_info(input, STANDARD.decode_ignore_invalid(true)).unwrap();
+            assert_eq!(bytes, lenient_bytes);
+            assert_eq!(metadata, lenient_metadata);
+        }
+
+        // padding in the fast-loop-sized prefix doesn't matter, only the trailing chunk can have it
+        let (_, metadata) =
+            decode_config_with_info("YWJjZGVmZ2hpamtsbW5vcHFyc3R1dnd4eXo=", STANDARD).unwrap();
+        assert_eq!(1, metadata.padding_len());
+    }
 
-            assert_eq!(
-                prefix_len + decoded_without_prefix.len(),
-                decoded_with_prefix.len()
-            );
-            assert_eq!(orig_data, decoded_without_prefix);
+    #[test]
+    fn decode_with_info_reports_skipped_bytes() {
+        let lenient = STANDARD.decode_ignore_invalid(true);
+        let trailing = STANDARD.decode_allow_trailing_whitespace(true);
+
+        for &(input, config, skipped_len) in &[
+            ("aGVsbG8=", STANDARD, 0),
+            ("aGVsbG8=", lenient, 0),
+            ("aGVs\r\nbG8=\r\n", lenient, 4),
+            ("a*GVsbG8=", lenient, 1),
+            ("aGVsbG8=\n", trailing, 1),
+            ("aGVsbG8 \t\r\n", trailing, 4),
+            (
+                "aG Vs\nbG8=\n",
+                lenient.decode_allow_trailing_whitespace(true),
+                3,
+            ),
+        ] {
+            let (bytes, metadata) = decode_config_with_info(input, config).unwrap();
+            assert_eq!(b"hello", &bytes[..]);
+            assert_eq!(skipped_len, metadata.skipped_len(), "{:?}", input);
+        }
 
-            // append plain decode onto prefix
-            prefix.append(&mut decoded_without_prefix);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng)
+                .decode_ignore_invalid(true)
+                .decode_constant_time(rng.gen());
+            let data: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+            let mut encoded = encode_config(&data, config).into_bytes();
+            let (_, expected) = decode_config_with_info(&encoded, config).unwrap();
+
+            let junk: Vec<u8> = b"\r\n\t *#"
+                .iter()
+                .cloned()
+                .filter(|&b| b != config.pad_byte && !config.char_set.is_valid_byte(b))
+                .collect();
+            let junk_len = rng.gen_range(0, 10);
+            for _ in 0..junk_len {
+                let index = rng.gen_range(0, encoded.len() + 1);
+                encoded.insert(index, *junk.choose(&mut rng).unwrap());
+            }
 
-            assert_eq!(prefix, decoded_with_prefix);
+            let (bytes, metadata) = decode_config_with_info(&encoded, config).unwrap();
+            assert_eq!(data, bytes);
+            assert_eq!(expected.padding_len(), metadata.padding_len());
+            assert_eq!(junk_len, metadata.skipped_len());
         }
     }
 
     #[test]
-    fn decode_into_slice_doesnt_clobber_existing_prefix_or_suffix() {
-        let mut orig_data = Vec::new();
-        let mut encoded_data = String::new();
-        let mut decode_buf = Vec::new();
-        let mut decode_buf_copy: Vec<u8> = Vec::new();
+    fn decode_str_distinguishes_errors() {
+        assert_eq!(
+            Ok(String::from("hello")),
+            decode_config_str("aGVsbG8=", STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeStrError::Decode(DecodeError::InvalidByte(1, b'*'))),
+            decode_config_str("a*VsbG8=", STANDARD)
+        );
 
-        let input_len_range = Uniform::new(0, 1000);
+        let invalid_utf8 = String::from_utf8(vec![0x68, 0xFF])
+            .unwrap_err()
+            .utf8_error();
+        assert_eq!(
+            Err(DecodeStrError::InvalidUtf8(invalid_utf8)),
+            decode_config_str("aP8=", STANDARD)
+        );
+    }
+
+    #[test]
+    fn invalid_byte_display_shows_printable_bytes_as_chars() {
+        assert_eq!(
+            "Invalid byte '*', offset 3.",
+            DecodeError::InvalidByte(3, b'*').to_string()
+        );
+        assert_eq!(
+            "Invalid byte ' ', offset 3.",
+            DecodeError::InvalidByte(3, b' ').to_string()
+        );
+        assert_eq!(
+            "Invalid byte 0x00, offset 2.",
+            DecodeError::InvalidByte(2, 0).to_string()
+        );
+        assert_eq!(
+            "Invalid byte 0xff, offset 0.",
+            DecodeError::InvalidByte(0, 0xFF).to_string()
+        );
+
+        let err = decode_config(b"YW\0j", STANDARD).unwrap_err();
+        assert_eq!("Invalid byte 0x00, offset 2.", err.to_string());
+    }
 
+    #[test]
+    fn decode_to_slice_decodes_whole_quads_only() {
         let mut rng = rand::rngs::SmallRng::from_entropy();
 
-        for _ in 0..10_000 {
-            orig_data.clear();
-            encoded_data.clear();
-            decode_buf.clear();
-            decode_buf_copy.clear();
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng).decode_constant_time(rng.gen());
+            let input: Vec<u8> = (0..rng.gen_range(0, 100) * 3).map(|_| rng.gen()).collect();
+            let encoded = encode_config(&input, config);
 
-            let input_len = input_len_range.sample(&mut rng);
+            let mut output = vec![0_u8; input.len()];
+            assert_eq!(
+                Ok(input.len()),
+                decode_to_slice(&encoded, config, &mut output)
+            );
+            assert_eq!(input, output);
+        }
 
-            for _ in 0..input_len {
-                orig_data.push(rng.gen());
-            }
+        let mut output = [0_u8; 6];
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_to_slice("aGVsbG8=", STANDARD, &mut output)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_to_slice("aGVs=G8h", STANDARD, &mut output)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidByte(3, b'*')),
+            decode_to_slice("aGV*bG8h", STANDARD, &mut output)
+        );
+        assert_eq!(
+            Err(DecodeError::OutputSliceTooSmall),
+            decode_to_slice("aGVsbG8h", STANDARD, &mut output[..5])
+        );
+        // trailing whitespace isn't trimmed, even if the config allows it
+        assert_eq!(
+            Err(DecodeError::InvalidByte(7, b'\n')),
+            decode_to_slice(
+                "aGVsbG8\n",
+                STANDARD.decode_allow_trailing_whitespace(true),
+                &mut output
+            )
+        );
+    }
 
-            let config = random_config(&mut rng);
-            encode_config_buf(&orig_data, config, &mut encoded_data);
-            assert_encode_sanity(&encoded_data, config, input_len);
+    #[test]
+    fn decode_allows_trailing_whitespace_only_at_end() {
+        let config = STANDARD.decode_allow_trailing_whitespace(true);
 
-            // fill the buffer with random garbage, long enough to have some room before and after
-            for _ in 0..5000 {
-                decode_buf.push(rng.gen());
-            }
+        for &input in &["aGVsbG8=\n", "aGVsbG8=\r\n", "aGVsbG8=  ", "aGVsbG8 \t\n"] {
+            assert_eq!(Ok(b"hello".to_vec()), decode_config(input, config));
+            assert_eq!(
+                Ok(b"hello".to_vec()),
+                decode_iter(input.bytes(), config),
+                "{:?}",
+                input
+            );
 
-            // keep a copy for later comparison
-            decode_buf_copy.extend(decode_buf.iter());
+            let mut buf = [0_u8; 8];
+            assert_eq!(Ok(5), decode_config_slice(input, config, &mut buf));
+            assert_eq!(b"hello", &buf[..5]);
 
-            let offset = 1000;
+            assert!(decode_config(input, STANDARD).is_err());
+        }
 
-            // decode into the non-empty buf
-            let decode_bytes_written =
-                decode_config_slice(&encoded_data, config, &mut decode_buf[offset..]).unwrap();
+        assert_eq!(Ok(Vec::new()), decode_config("\n", config));
+        for &input in &["aGVs\nbG8", "aGVsbG\n=", " aGVsbG8"] {
+            let offset = input.find(char::is_whitespace).unwrap();
+            let byte = input.as_bytes()[offset];
 
-            assert_eq!(orig_data.len(), decode_bytes_written);
             assert_eq!(
-                orig_data,
-                &decode_buf[offset..(offset + decode_bytes_written)]
+                Err(DecodeError::InvalidByte(offset, byte)),
+                decode_config(input, config)
             );
-            assert_eq!(&decode_buf_copy[0..offset], &decode_buf[0..offset]);
             assert_eq!(
-                &decode_buf_copy[offset + decode_bytes_written..],
-                &decode_buf[offset + decode_bytes_written..]
+                Err(DecodeError::InvalidByte(offset, byte)),
+                decode_iter(input.bytes(), config)
             );
         }
     }
 
     #[test]
-    fn decode_into_slice_fits_in_precisely_sized_slice() {
+    fn decode_trailing_whitespace_keeps_whitespace_pad_byte() {
+        let config = STANDARD
+            .pad_byte(b' ')
+            .decode_allow_trailing_whitespace(true)
+            .decode_padding_mode(DecodePaddingMode::RequireCanonical);
+
+        for &input in &["Zg  ", "Zg  \r\n"] {
+            assert_eq!(Ok(b"f".to_vec()), decode_config(input, config));
+            assert_eq!(Ok(b"f".to_vec()), decode_iter(input.bytes(), config));
+
+            let mut decoder = crate::StreamDecoder::new(config);
+            assert_eq!(Ok(Vec::new()), decoder.feed(input.as_bytes()));
+            assert_eq!(Ok(b"f".to_vec()), decoder.finish());
+        }
+
+        // whitespace that isn't the padding byte can't come before padding
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'\n')),
+            decode_config("Zg\n ", config)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'\n')),
+            decode_iter("Zg\n ".bytes(), config)
+        );
+    }
+
+    #[test]
+    fn decode_in_place_matches_decode_config() {
         let mut orig_data = Vec::new();
         let mut encoded_data = String::new();
-        let mut decode_buf = Vec::new();
-
         let input_len_range = Uniform::new(0, 1000);
-
         let mut rng = rand::rngs::SmallRng::from_entropy();
 
+        let padding_modes = [
+            DecodePaddingMode::Indifferent,
+            DecodePaddingMode::RequireCanonical,
+            DecodePaddingMode::IgnoreTrailing,
+        ];
+
         for _ in 0..10_000 {
             orig_data.clear();
             encoded_data.clear();
-            decode_buf.clear();
 
             let input_len = input_len_range.sample(&mut rng);
-
             for _ in 0..input_len {
                 orig_data.push(rng.gen());
             }
 
-            let config = random_config(&mut rng);
+            let max_len = if rng.gen() {
+                Some(rng.gen_range(0, input_len + 1))
+            } else {
+                None
+            };
+            let config = random_config(&mut rng)
+                .decode_ignore_invalid(rng.gen())
+                .decode_allow_trailing_whitespace(rng.gen())
+                .decode_constant_time(rng.gen())
+                .decode_padding_mode(*padding_modes.choose(&mut rng).unwrap())
+                .decode_max_len(max_len);
             encode_config_buf(&orig_data, config, &mut encoded_data);
-            assert_encode_sanity(&encoded_data, config, input_len);
-
-            decode_buf.resize(input_len, 0);
-
-            // decode into the non-empty buf
-            let decode_bytes_written =
-                decode_config_slice(&encoded_data, config, &mut decode_buf[..]).unwrap();
+            let mut encoded_bytes = encoded_data.clone().into_bytes();
+
+            // usually break it somehow, to check the errors match too
+            if rng.gen() {
+                let index = rng.gen_range(0, encoded_bytes.len() + 1);
+                let byte = *[config.pad_byte, b'\n', b'*', b'A']
+                    .choose(&mut rng)
+                    .unwrap();
+                encoded_bytes.insert(index, byte);
+            }
 
-            assert_eq!(orig_data.len(), decode_bytes_written);
-            assert_eq!(orig_data, decode_buf);
+            let mut buf = encoded_bytes.clone();
+            assert_eq!(
+                decode_config(&encoded_bytes, config),
+                decode_in_place(&mut buf, config).map(|_| buf),
+                "{:?} {:?}",
+                String::from_utf8_lossy(&encoded_bytes),
+                config
+            );
         }
     }
 
     #[test]
-    fn detect_invalid_last_symbol_two_bytes() {
-        let decode =
-            |input, forgiving| decode_config(input, STANDARD.decode_allow_trailing_bits(forgiving));
+    fn decode_in_place_errors() {
+        let in_place = |s: &str, config| decode_in_place(&mut s.as_bytes().to_vec(), config);
 
-        // example from https://github.com/marshallpierce/rust-base64/issues/75
-        assert!(decode("iYU=", false).is_ok());
-        // trailing 01
+        assert_eq!(Ok(0), in_place("", STANDARD));
         assert_eq!(
-            Err(DecodeError::InvalidLastSymbol(2, b'V')),
-            decode("iYV=", false)
+            Err(DecodeError::InvalidByte(1030, b'*')),
+            in_place(&format!("{}YW*j", "A".repeat(1028)), STANDARD)
         );
-        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
-        // trailing 10
         assert_eq!(
-            Err(DecodeError::InvalidLastSymbol(2, b'W')),
-            decode("iYW=", false)
+            Err(DecodeError::InvalidLastSymbol(1029, b'R')),
+            in_place(&format!("{}YR==", "A".repeat(1028)), STANDARD)
         );
-        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
-        // trailing 11
         assert_eq!(
-            Err(DecodeError::InvalidLastSymbol(2, b'X')),
-            decode("iYX=", false)
+            Err(DecodeError::InvalidPadding),
+            in_place("YQ==YWJj", STANDARD)
         );
-        assert_eq!(Ok(vec![137, 133]), decode("iYV=", true));
-
-        // also works when there are 2 quads in the last block
         assert_eq!(
-            Err(DecodeError::InvalidLastSymbol(6, b'X')),
-            decode("AAAAiYX=", false)
+            Err(DecodeError::InvalidPadding),
+            in_place(&format!("YQ{}", "=".repeat(46)), STANDARD)
+        );
+        assert_eq!(Err(DecodeError::InvalidLength), in_place("YQ===", STANDARD));
+        assert_eq!(
+            Err(DecodeError::TooLong),
+            in_place("YWJjZA==", STANDARD.decode_max_len(Some(3)))
+        );
+        assert_eq!(
+            Ok(4),
+            in_place("YW\nJj*ZA==", STANDARD.decode_ignore_invalid(true))
         );
-        assert_eq!(Ok(vec![0, 0, 0, 137, 133]), decode("AAAAiYX=", true));
     }
 
     #[test]
-    fn detect_invalid_last_symbol_one_byte() {
-        // 0xFF -> "/w==", so all letters > w, 0-9, and '+', '/' should get InvalidLastSymbol
+    fn kind_ignores_offset_and_byte() {
+        assert_eq!(
+            DecodeErrorKind::InvalidByte,
+            decode_config("YW*j", STANDARD).unwrap_err().kind()
+        );
+        assert_eq!(
+            DecodeError::InvalidByte(0, 0).kind(),
+            DecodeError::InvalidByte(7, b'*').kind()
+        );
+        assert_eq!(
+            DecodeErrorKind::InvalidLastSymbol,
+            decode_config("YWJ=", STANDARD).unwrap_err().kind()
+        );
+        assert_eq!(
+            "invalid padding",
+            DecodeError::InvalidPadding.kind().to_string()
+        );
+        assert_eq!("too long", DecodeError::TooLong.kind().as_str());
+    }
 
-        assert!(decode("/w==").is_ok());
-        // trailing 01
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'x')), decode("/x=="));
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'z')), decode("/z=="));
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'0')), decode("/0=="));
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'9')), decode("/9=="));
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'+')), decode("/+=="));
-        assert_eq!(Err(DecodeError::InvalidLastSymbol(1, b'/')), decode("//=="));
+    #[test]
+    fn decode_short_input_reports_each_invalid_byte() {
+        // 24 symbols are mostly decoded a quad at a time after the fast loop
+        let encoded = encode_config([0xA5_u8; 18], STANDARD).into_bytes();
+        assert_eq!(24, encoded.len());
+
+        for i in 0..encoded.len() {
+            let mut corrupted = encoded.clone();
+            corrupted[i] = b'*';
+            assert_eq!(
+                Err(DecodeError::InvalidByte(i, b'*')),
+                decode_config(&corrupted, STANDARD)
+            );
+        }
 
-        // also works when there are 2 quads in the last block
+        // padding in a whole quad still gets the padding error
         assert_eq!(
-            Err(DecodeError::InvalidLastSymbol(5, b'x')),
-            decode("AAAA/x==")
+            Err(DecodeError::InvalidPadding),
+            decode_config("AAAAAAAAAAAAAAAA=AAAAAAA", STANDARD)
         );
     }
 
     #[test]
-    fn detect_invalid_last_symbol_every_possible_three_symbols() {
-        let mut base64_to_bytes = ::std::collections::HashMap::new();
+    fn decode_url_escaped_padding_matches_unescaped() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
 
-        let mut bytes = [0_u8; 2];
-        for b1 in 0_u16..256 {
-            bytes[0] = b1 as u8;
-            for b2 in 0_u16..256 {
-                bytes[1] = b2 as u8;
-                let mut b64 = vec![0_u8; 4];
-                assert_eq!(4, encode_config_slice(&bytes, STANDARD, &mut b64[..]));
-                let mut v = ::std::vec::Vec::with_capacity(2);
-                v.extend_from_slice(&bytes[..]);
+        let padding_modes = [
+            DecodePaddingMode::Indifferent,
+            DecodePaddingMode::RequireCanonical,
+            DecodePaddingMode::IgnoreTrailing,
+        ];
 
-                assert!(base64_to_bytes.insert(b64, v).is_none());
+        for _ in 0..10_000 {
+            let data: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+            let config = random_config(&mut rng)
+                .decode_ignore_invalid(rng.gen())
+                .decode_allow_trailing_whitespace(rng.gen())
+                .decode_constant_time(rng.gen())
+                .decode_padding_mode(*padding_modes.choose(&mut rng).unwrap());
+            let mut unescaped = encode_config(&data, config).into_bytes();
+
+            // sometimes break it, to check the errors match too
+            if rng.gen() {
+                let index = rng.gen_range(0, unescaped.len() + 1);
+                let byte = *[config.pad_byte, b'\n', b'*', b'A']
+                    .choose(&mut rng)
+                    .unwrap();
+                unescaped.insert(index, byte);
             }
-        }
 
-        // every possible combination of symbols must either decode to 2 bytes or get InvalidLastSymbol
-
-        let mut symbols = [0_u8; 4];
-        for &s1 in STANDARD.char_set.encode_table().iter() {
-            symbols[0] = s1;
-            for &s2 in STANDARD.char_set.encode_table().iter() {
-                symbols[1] = s2;
-                for &s3 in STANDARD.char_set.encode_table().iter() {
-                    symbols[2] = s3;
-                    symbols[3] = PAD_BYTE;
+            // escape up to 2 of the padding bytes at the end, in either case
+            let trailing_pads = unescaped
+                .iter()
+                .rev()
+                .take(2)
+                .take_while(|&&b| b == config.pad_byte)
+                .count();
+            let mut escaped = unescaped[..(unescaped.len() - trailing_pads)].to_vec();
+            for _ in 0..trailing_pads {
+                let hex = if rng.gen() {
+                    format!("%{:02X}", config.pad_byte)
+                } else {
+                    format!("%{:02x}", config.pad_byte)
+                };
+                escaped.extend_from_slice(hex.as_bytes());
+            }
 
-                    match base64_to_bytes.get(&symbols[..]) {
-                        Some(bytes) => {
-                            assert_eq!(Ok(bytes.to_vec()), decode_config(&symbols, STANDARD))
-                        }
-                        None => assert_eq!(
-                            Err(DecodeError::InvalidLastSymbol(2, s3)),
-                            decode_config(&symbols[..], STANDARD)
-                        ),
-                    }
-                }
+            let escaping_config = config.decode_url_escaped_padding(true);
+            if config.char_set.is_valid_byte(b'%') {
+                // escapes aren't recognized, so it's decoded as it is
+                assert_eq!(
+                    decode_config(&escaped, config),
+                    decode_config(&escaped, escaping_config)
+                );
+                continue;
             }
-        }
-    }
 
-    #[test]
-    fn detect_invalid_last_symbol_every_possible_two_symbols() {
-        let mut base64_to_bytes = ::std::collections::HashMap::new();
+            let expected = decode_config(&unescaped, config);
+            assert_eq!(
+                expected,
+                decode_config(&escaped, escaping_config),
+                "{:?} {:?}",
+                String::from_utf8_lossy(&escaped),
+                config
+            );
+            assert_eq!(
+                decode_config_with_info(&unescaped, config),
+                decode_config_with_info(&escaped, escaping_config)
+            );
 
-        for b in 0_u16..256 {
-            let mut b64 = vec![0_u8; 4];
-            assert_eq!(4, encode_config_slice(&[b as u8], STANDARD, &mut b64[..]));
-            let mut v = ::std::vec::Vec::with_capacity(1);
-            v.push(b as u8);
+            let mut unescaped_slice = vec![0; unescaped.len()];
+            let mut escaped_slice = vec![0; unescaped.len()];
+            assert_eq!(
+                decode_config_slice(&unescaped, config, &mut unescaped_slice),
+                decode_config_slice(&escaped, escaping_config, &mut escaped_slice)
+            );
+            assert_eq!(unescaped_slice, escaped_slice);
 
-            assert!(base64_to_bytes.insert(b64, v).is_none());
+            let mut buf = escaped.clone();
+            assert_eq!(
+                expected,
+                decode_in_place(&mut buf, escaping_config).map(|len| buf[..len].to_vec())
+            );
         }
+    }
 
-        // every possible combination of symbols must either decode to 1 byte or get InvalidLastSymbol
-
-        let mut symbols = [0_u8; 4];
-        for &s1 in STANDARD.char_set.encode_table().iter() {
-            symbols[0] = s1;
-            for &s2 in STANDARD.char_set.encode_table().iter() {
-                symbols[1] = s2;
-                symbols[2] = PAD_BYTE;
-                symbols[3] = PAD_BYTE;
+    #[test]
+    fn decode_url_escaped_padding_only_at_end() {
+        let config = STANDARD.decode_url_escaped_padding(true);
 
-                match base64_to_bytes.get(&symbols[..]) {
-                    Some(bytes) => {
-                        assert_eq!(Ok(bytes.to_vec()), decode_config(&symbols, STANDARD))
-                    }
-                    None => assert_eq!(
-                        Err(DecodeError::InvalidLastSymbol(1, s2)),
-                        decode_config(&symbols[..], STANDARD)
-                    ),
-                }
-            }
-        }
+        assert_eq!(Ok(b"f".to_vec()), decode_config("Zg%3D%3d", config));
+        assert_eq!(Ok(b"fo".to_vec()), decode_config("Zm8%3D", config));
+        assert_eq!(Ok(b"f".to_vec()), decode_config("Zg=%3D", config));
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'%')),
+            decode_config("Zg%3D%3D", STANDARD)
+        );
+        // only 2 are taken as padding
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'%')),
+            decode_config("Zg%3D%3D%3D", config)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'%')),
+            decode_config("Zg%3DZm8", config)
+        );
+        // still checked like the padding it stands for
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config("Zm9v%3D%3D", config)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config(
+                "Zg%3D",
+                config.decode_padding_mode(DecodePaddingMode::RequireCanonical)
+            )
+        );
+        // `%3D` could be symbols in BinHex, so they're left alone
+        assert_eq!(
+            Ok(decode_config("%3D%", crate::BINHEX).unwrap()),
+            decode_config("%3D%", crate::BINHEX.decode_url_escaped_padding(true))
+        );
+        assert_eq!(
+            Ok(b"f".to_vec()),
+            decode_config(
+                "Zg%3D%3D\r\n",
+                config.decode_allow_trailing_whitespace(true)
+            )
+        );
     }
 }
@@ -0,0 +1,274 @@
+use crate::{decode::BytewiseDecoder, max_decoded_len, Config, DecodeError};
+use core::iter::{self, FusedIterator};
+
+/// An iterator that decodes base64 from an iterator of bytes, one decoded byte at a time.
+///
+/// Nothing is decoded until it's asked for, so this can check the start of some base64, like a
+/// magic number, without decoding the rest of it. Input is read only as far as needed to yield the
+/// next byte, and the first error ends the iteration: it's yielded as an `Err`, after which the
+/// iterator returns `None`. Collecting into a `Result<Vec<u8>, DecodeError>` gives the same
+/// result as `decode_iter`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let png = "iVBORw0KGgoAAAANSUhEUg==";
+///     let magic: Result<Vec<u8>, _> =
+///         base64::DecodeIter::new(png.bytes(), base64::STANDARD).take(4).collect();
+///     assert_eq!(Ok(b"\x89PNG".to_vec()), magic);
+///
+///     let mut bytes = base64::DecodeIter::new("YWJj*A==".bytes(), base64::STANDARD);
+///     assert_eq!(Some(Ok(b'a')), bytes.next());
+///     assert_eq!(
+///         Err(base64::DecodeError::InvalidByte(4, b'*')),
+///         bytes.collect::<Result<Vec<u8>, _>>()
+///     );
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DecodeIter<I> {
+    input: I,
+    config: Config,
+    // only used for custom alphabets, built the first time it's needed
+    custom_decode_table: Option<[u8; 256]>,
+    /// None once the input has ended or an error has been returned
+    state: Option<BytewiseDecoder>,
+    /// The bytes decoded from the most recent quad
+    decoded: [u8; 3],
+    // index of the next byte of `decoded` to yield
+    decoded_index: usize,
+    // how many bytes of `decoded` are valid
+    decoded_len: usize,
+    // how many bytes have been decoded in total, for `Config::decode_max_len`
+    total_decoded_len: usize,
+}
+
+impl<I: Iterator<Item = u8>> DecodeIter<I> {
+    /// Create an iterator over the bytes decoded from `input` with the provided config.
+    pub fn new<T: IntoIterator<IntoIter = I, Item = u8>>(
+        input: T,
+        config: Config,
+    ) -> DecodeIter<I> {
+        DecodeIter {
+            input: input.into_iter(),
+            config,
+            custom_decode_table: None,
+            state: Some(BytewiseDecoder::default()),
+            decoded: [0; 3],
+            decoded_index: 0,
+            decoded_len: 0,
+            total_decoded_len: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for DecodeIter<I> {
+    type Item = Result<u8, DecodeError>;
+
+    fn next(&mut self) -> Option<Result<u8, DecodeError>> {
+        while self.decoded_index == self.decoded_len {
+            // don't read any more of the input once it's done
+            let state = self.state.as_mut()?;
+            self.decoded_index = 0;
+            self.decoded_len = 0;
+
+            let config = self.config;
+            let decode_table = config.char_set.decode_table(&mut self.custom_decode_table);
+            let decoded = &mut self.decoded;
+            let decoded_len = &mut self.decoded_len;
+            let total_decoded_len = &mut self.total_decoded_len;
+            let mut write = |bytes: &[u8]| {
+                if let Some(max_len) = config.decode_max_len {
+                    if max_len - *total_decoded_len < bytes.len() {
+                        return Err(DecodeError::TooLong);
+                    }
+                }
+
+                decoded[..bytes.len()].copy_from_slice(bytes);
+                *decoded_len = bytes.len();
+                *total_decoded_len += bytes.len();
+                Ok(())
+            };
+
+            let result = match self.input.next() {
+                Some(b) => state.feed(iter::once(b), config, decode_table, &mut write),
+                // padding and any partial quad can only be checked once there's no more input
+                None => self.state.take()?.finish(config, &mut write).map(|_| ()),
+            };
+
+            if let Err(e) = result {
+                self.state = None;
+                return Some(Err(e));
+            }
+        }
+
+        let b = self.decoded[self.decoded_index];
+        self.decoded_index += 1;
+
+        Some(Ok(b))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.decoded_len - self.decoded_index;
+        if self.state.is_none() {
+            return (buffered, Some(buffered));
+        }
+
+        // up to 3 symbols of a partial quad may be held by the state, and there may be an error
+        let upper = self
+            .input
+            .size_hint()
+            .1
+            .and_then(|len| len.checked_add(3))
+            .and_then(|len| (buffered + 1).checked_add(max_decoded_len(len)));
+
+        (buffered, upper)
+    }
+}
+
+impl<I: Iterator<Item = u8>> FusedIterator for DecodeIter<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_iter, encode_config_buf,
+        tests::{escape_padding_and_add_whitespace, random_config, random_decode_options},
+        DecodePaddingMode, STANDARD,
+    };
+
+    use rand::{
+        distributions::{Distribution, Uniform},
+        seq::SliceRandom,
+        FromEntropy, Rng,
+    };
+
+    #[test]
+    fn decode_iter_struct_matches_decode_iter() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let input_len_range = Uniform::new(0, 100);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let max_len = if rng.gen() {
+                Some(rng.gen_range(0, input_len + 1))
+            } else {
+                None
+            };
+            let config =
+                random_decode_options(random_config(&mut rng), &mut rng).decode_max_len(max_len);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            let mut encoded_bytes = encoded_data.clone().into_bytes();
+            let mut valid = escape_padding_and_add_whitespace(
+                &mut encoded_bytes,
+                config.decode_url_escaped_padding(false),
+                &mut rng,
+            );
+
+            // usually break it somehow, to check the errors match too
+            if rng.gen() {
+                let index = rng.gen_range(0, encoded_bytes.len() + 1);
+                let byte = *[config.pad_byte, b'\n', b'*', b'A']
+                    .choose(&mut rng)
+                    .unwrap();
+                encoded_bytes.insert(index, byte);
+                valid = false;
+            }
+
+            // escaped padding is only recognized when decoding a whole slice at once
+            let expected = decode_iter(
+                encoded_bytes.iter().cloned(),
+                config.decode_url_escaped_padding(false),
+            );
+            // valid input decodes to what was encoded, unless it's too long or missing padding
+            // that's required
+            let too_long = max_len.map_or(false, |max_len| max_len < input_len);
+            let missing_padding = !config.pad
+                && input_len % 3 != 0
+                && config.decode_padding_mode == DecodePaddingMode::RequireCanonical;
+            if valid && !too_long && !missing_padding {
+                assert_eq!(Ok(orig_data.clone()), expected);
+            }
+            let mut iter = DecodeIter::new(encoded_bytes.iter().cloned(), config);
+            assert_eq!(expected, iter.by_ref().collect::<Result<Vec<u8>, _>>());
+            assert_eq!(None, iter.next());
+        }
+    }
+
+    #[test]
+    fn decode_iter_struct_is_lazy() {
+        // only the first quad needs to be read for its bytes
+        let mut input_read = 0;
+        let input = "YWJjZGVm".bytes().inspect(|_| input_read += 1);
+        assert_eq!(3, DecodeIter::new(input, STANDARD).take(3).count());
+        assert_eq!(4, input_read);
+    }
+
+    #[test]
+    fn decode_iter_struct_yields_bytes_before_error() {
+        let mut iter = DecodeIter::new("YWJjZGVm*".bytes(), STANDARD);
+
+        assert_eq!(Some(Ok(b'a')), iter.next());
+        assert_eq!(Some(Ok(b'b')), iter.next());
+        assert_eq!(Some(Ok(b'c')), iter.next());
+        assert_eq!(Some(Ok(b'd')), iter.next());
+        assert_eq!(Some(Ok(b'e')), iter.next());
+        assert_eq!(Some(Ok(b'f')), iter.next());
+        assert_eq!(Some(Err(DecodeError::InvalidByte(8, b'*'))), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn decode_iter_struct_stops_reading_input_when_done() {
+        let mut input = "YW*jZGVm".bytes();
+        {
+            let mut iter = DecodeIter::new(input.by_ref(), STANDARD);
+            assert_eq!(Some(Err(DecodeError::InvalidByte(2, b'*'))), iter.next());
+            assert_eq!(None, iter.next());
+            assert_eq!(None, iter.next());
+        }
+        assert_eq!(Some(b'j'), input.next());
+
+        let mut input = "YQ==".bytes().chain(iter::repeat(b'A'));
+        {
+            let mut iter = DecodeIter::new(input.by_ref(), STANDARD);
+            assert_eq!(Some(Err(DecodeError::InvalidPadding)), iter.next());
+            assert_eq!(None, iter.next());
+        }
+        assert_eq!(Some(b'A'), input.next());
+    }
+
+    #[test]
+    fn decode_iter_struct_padding_ends_input() {
+        let decode = |s: &'static str| DecodeIter::new(s.bytes(), STANDARD).collect();
+
+        assert_eq!(Ok(b"ab".to_vec()), decode("YWI="));
+        assert_eq!(Err(DecodeError::InvalidPadding), decode("YWI=YWJj"));
+        assert_eq!(Err(DecodeError::InvalidPadding), decode("YWI=="));
+        assert_eq!(Ok(Vec::new()), decode(""));
+    }
+
+    #[test]
+    fn decode_iter_struct_size_hint() {
+        let mut iter = DecodeIter::new("YWJjZA==".bytes(), STANDARD);
+        assert_eq!((0, Some(9)), iter.size_hint());
+
+        assert_eq!(Some(Ok(b'a')), iter.next());
+        assert_eq!((2, Some(8)), iter.size_hint());
+
+        assert_eq!(4, iter.by_ref().count() + 1);
+        assert_eq!((0, Some(0)), iter.size_hint());
+    }
+}
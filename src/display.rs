@@ -9,10 +9,10 @@
 //! assert_eq!("base64: AAECAw==", format!("base64: {}", wrapper));
 //! ```
 
-use super::chunked_encoder::ChunkedEncoder;
+use super::chunked_encoder::{ChunkedEncoder, FmtSink};
 use super::Config;
+use core::fmt;
 use core::fmt::{Display, Formatter};
-use core::{fmt, str};
 
 /// A convenience wrapper for base64'ing bytes into a format string without heap allocation.
 pub struct Base64Display<'a> {
@@ -22,7 +22,7 @@ pub struct Base64Display<'a> {
 
 impl<'a> Base64Display<'a> {
     /// Create a `Base64Display` with the provided config.
-    pub fn with_config(bytes: &[u8], config: Config) -> Base64Display {
+    pub fn with_config(bytes: &[u8], config: Config) -> Base64Display<'_> {
         Base64Display {
             bytes,
             chunked_encoder: ChunkedEncoder::new(config),
@@ -32,26 +32,11 @@ impl<'a> Base64Display<'a> {
 
 impl<'a> Display for Base64Display<'a> {
     fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
-        let mut sink = FormatterSink { f: formatter };
+        let mut sink = FmtSink::new(formatter);
         self.chunked_encoder.encode(self.bytes, &mut sink)
     }
 }
 
-struct FormatterSink<'a, 'b: 'a> {
-    f: &'a mut Formatter<'b>,
-}
-
-impl<'a, 'b: 'a> super::chunked_encoder::Sink for FormatterSink<'a, 'b> {
-    type Error = fmt::Error;
-
-    fn write_encoded_bytes(&mut self, encoded: &[u8]) -> Result<(), Self::Error> {
-        // Avoid unsafe. If max performance is needed, write your own display wrapper that uses
-        // unsafe here to gain about 10-15%.
-        self.f
-            .write_str(str::from_utf8(encoded).expect("base64 data was not utf8"))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::super::chunked_encoder::tests::{
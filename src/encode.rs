@@ -1,9 +1,14 @@
 #[cfg(any(feature = "alloc", feature = "std", test))]
-use crate::{chunked_encoder, STANDARD};
-use crate::{Config, PAD_BYTE};
+use crate::{chunked_encoder::StringSink, STANDARD};
+use crate::{
+    chunked_encoder::{ChunkedEncoder, FmtSink},
+    Config,
+};
 #[cfg(any(feature = "alloc", feature = "std", test))]
-use alloc::{string::String, vec};
-use core::convert::TryInto;
+use alloc::{borrow::Cow, boxed::Box, string::String, vec, vec::Vec};
+use core::{convert::TryInto, fmt};
+#[cfg(any(feature = "std", test))]
+use std::error;
 
 ///Encode arbitrary octets as base64.
 ///Returns a String.
@@ -27,6 +32,8 @@ pub fn encode<T: AsRef<[u8]>>(input: T) -> String {
 ///Encode arbitrary octets as base64.
 ///Returns a String.
 ///
+///The String is allocated at exactly the encoded length, so it has no spare capacity.
+///
 ///# Example
 ///
 ///```rust
@@ -42,14 +49,120 @@ pub fn encode<T: AsRef<[u8]>>(input: T) -> String {
 ///```
 #[cfg(any(feature = "alloc", feature = "std", test))]
 pub fn encode_config<T: AsRef<[u8]>>(input: T, config: Config) -> String {
-    let mut buf = match encoded_size(input.as_ref().len(), config) {
+    match try_encode_config(input, config) {
+        Ok(encoded) => encoded,
+        Err(_) => panic!("integer overflow when calculating buffer size"),
+    }
+}
+
+///Encode arbitrary octets as base64.
+///Returns a Result containing a String.
+///
+///This is `encode_config`, but returns `EncodeError::LengthOverflow` rather than panicking if the
+///encoded length doesn't fit in a `usize`. That can only happen on targets where `usize` is 32 bits
+///or smaller, with inputs of more than about 3 GiB.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let b64 = base64::try_encode_config(b"hello world~", base64::STANDARD);
+///    assert_eq!(Ok("aGVsbG8gd29ybGR+".to_string()), b64);
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn try_encode_config<T: AsRef<[u8]>>(input: T, config: Config) -> Result<String, EncodeError> {
+    let mut buf = match encoded_len(input.as_ref().len(), config) {
         Some(n) => vec![0; n],
-        None => panic!("integer overflow when calculating buffer size"),
+        None => return Err(EncodeError::LengthOverflow),
     };
 
     encode_with_padding(input.as_ref(), config, buf.len(), &mut buf[..]);
 
-    String::from_utf8(buf).expect("Invalid UTF8")
+    Ok(String::from_utf8(buf).expect("Invalid UTF8"))
+}
+
+/// Errors that can occur while encoding.
+#[cfg(any(feature = "alloc", feature = "std", test))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The encoded output would be longer than `usize::MAX` bytes.
+    LengthOverflow,
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::LengthOverflow => write!(f, "Encoded length would overflow usize."),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for EncodeError {
+    fn description(&self) -> &str {
+        match *self {
+            EncodeError::LengthOverflow => "length overflow",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
+}
+
+///Encode arbitrary octets as base64.
+///Returns a Box<str>.
+///
+///Like `encode_config`, this allocates exactly the encoded length, but the `Box<str>` makes that
+///part of the type, and is a word smaller than a String. It suits encoded values that are kept
+///around, like stored tokens.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let b64: Box<str> = base64::encode_config_boxed(b"hello world~", base64::STANDARD);
+///    assert_eq!("aGVsbG8gd29ybGR+", &*b64);
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn encode_config_boxed<T: AsRef<[u8]>>(input: T, config: Config) -> Box<str> {
+    // capacity is already exact, so this doesn't reallocate
+    encode_config(input, config).into_boxed_str()
+}
+
+///Encode arbitrary octets as base64.
+///Returns a Cow<'static, str>.
+///
+///Empty input is returned as `Cow::Borrowed("")` without touching the encoder, and anything else is
+///encoded with `encode_config` into `Cow::Owned`. It suits fields that are often empty and end up
+///somewhere that takes a `Cow`, like a header map or a serializer.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///use std::borrow::Cow;
+///
+///fn main() {
+///    assert_eq!(Cow::Borrowed(""), base64::encode_cow(b"", base64::STANDARD));
+///    assert_eq!("aGk=", base64::encode_cow(b"hi", base64::STANDARD));
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn encode_cow<T: AsRef<[u8]>>(input: T, config: Config) -> Cow<'static, str> {
+    if input.as_ref().is_empty() {
+        return Cow::Borrowed("");
+    }
+
+    Cow::Owned(encode_config(input, config))
 }
 
 ///Encode arbitrary octets as base64.
@@ -72,16 +185,142 @@ pub fn encode_config<T: AsRef<[u8]>>(input: T, config: Config) -> String {
 ///```
 #[cfg(any(feature = "alloc", feature = "std", test))]
 pub fn encode_config_buf<T: AsRef<[u8]>>(input: T, config: Config, buf: &mut String) {
-    let input_bytes = input.as_ref();
+    let _ = encode_into(input, config, buf);
+}
+
+///Encode arbitrary octets as base64.
+///Replaces the contents of the supplied output buffer, reusing its capacity.
+///
+///Unlike `encode_config_buf`, anything already in `buf` is discarded rather than appended to.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let mut buf = String::from("stale");
+///    base64::encode_config_replace(b"hello world~", base64::STANDARD, &mut buf);
+///    assert_eq!("aGVsbG8gd29ybGR+", buf);
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn encode_config_replace<T: AsRef<[u8]>>(input: T, config: Config, buf: &mut String) {
+    buf.clear();
+    encode_config_buf(input, config, buf);
+}
+
+///Encode arbitrary octets as base64 into a scratch buffer, reusing its capacity.
+///Returns a `&str` of the encoded value, borrowed from `scratch`.
+///
+///This is `encode_config_replace` for when the result is only needed briefly, such as to look it
+///up in a map. Since the result borrows `scratch`, it can't be held on to past the next use of
+///the buffer.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let mut scratch = String::new();
+///    for (input, expected) in [(&b"hello"[..], "aGVsbG8="), (&b"hi"[..], "aGk=")].iter() {
+///        let encoded = base64::encode_config_into_str(input, base64::STANDARD, &mut scratch);
+///        assert_eq!(*expected, encoded);
+///    }
+///}
+///```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn encode_config_into_str<T: AsRef<[u8]>>(
+    input: T,
+    config: Config,
+    scratch: &mut String,
+) -> &str {
+    encode_config_replace(input, config, scratch);
+    scratch
+}
+
+/// Encodes with a fixed `Config` into a buffer that's reused between calls, so encoding many
+/// small inputs doesn't allocate a new `String` each time.
+///
+/// This is the same as calling `encode_config_buf` with a `String` that's cleared each time, but
+/// without having to keep track of the buffer.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut encoder = base64::Encoder::new(base64::STANDARD);
+///
+///     for (input, expected) in [(&b"hello"[..], "aGVsbG8="), (&b"world"[..], "d29ybGQ=")].iter() {
+///         assert_eq!(*expected, encoder.encode(input));
+///     }
+/// }
+/// ```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+#[derive(Clone, Debug)]
+pub struct Encoder {
+    config: Config,
+    buf: String,
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl Encoder {
+    /// Create an `Encoder` that will encode with the provided config.
+    pub fn new(config: Config) -> Encoder {
+        Encoder {
+            config,
+            buf: String::new(),
+        }
+    }
 
-    {
-        let mut sink = chunked_encoder::StringSink::new(buf);
-        let encoder = chunked_encoder::ChunkedEncoder::new(config);
+    /// Encode `input`, returning the encoded base64.
+    ///
+    /// The returned `&str` borrows the `Encoder`'s buffer, which is overwritten by the next call.
+    pub fn encode<T: AsRef<[u8]>>(&mut self, input: T) -> &str {
+        self.buf.clear();
+        encode_config_buf(input, self.config, &mut self.buf);
 
-        encoder
-            .encode(input_bytes, &mut sink)
-            .expect("Writing to a String shouldn't fail")
+        &self.buf
     }
+
+    /// Release the memory held by the buffer, e.g. after encoding an unusually large input.
+    pub fn reset(&mut self) {
+        self.buf = String::new();
+    }
+}
+
+/// Encode arbitrary octets as base64.
+/// Writes into anything that implements `fmt::Write`, a chunk at a time.
+///
+/// Nothing is allocated, so this works without the `alloc` feature. An error is only returned if
+/// `output` returns one.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// use std::fmt::Write;
+///
+/// fn main() {
+///     let mut s = String::from("data: ");
+///     base64::encode_config_fmt(b"hello internet!", base64::STANDARD, &mut s).unwrap();
+///     s.write_str("\n").unwrap();
+///
+///     assert_eq!("data: aGVsbG8gaW50ZXJuZXQh\n", s);
+/// }
+/// ```
+pub fn encode_config_fmt<T: AsRef<[u8]>, W: fmt::Write + ?Sized>(
+    input: T,
+    config: Config,
+    output: &mut W,
+) -> fmt::Result {
+    let mut sink = FmtSink::new(output);
+
+    ChunkedEncoder::new(config).encode(input.as_ref(), &mut sink)
 }
 
 /// Encode arbitrary octets as base64.
@@ -92,7 +331,8 @@ pub fn encode_config_buf<T: AsRef<[u8]>>(input: T, config: Config, buf: &mut Str
 ///
 /// # Panics
 ///
-/// If `output` is too small to hold the encoded version of `input`, a panic will result.
+/// If `output` is too small to hold the encoded version of `input`, a panic will result. Use
+/// `encoded_len` to size it.
 ///
 /// # Example
 ///
@@ -103,7 +343,7 @@ pub fn encode_config_buf<T: AsRef<[u8]>>(input: T, config: Config, buf: &mut Str
 ///     let s = b"hello internet!";
 ///     let mut buf = Vec::new();
 ///     // make sure we'll have a slice big enough for base64 + padding
-///     buf.resize(s.len() * 4 / 3 + 4, 0);
+///     buf.resize(base64::encoded_len(s.len(), base64::STANDARD).unwrap(), 0);
 ///
 ///     let bytes_written = base64::encode_config_slice(s,
 ///                             base64::STANDARD, &mut buf);
@@ -115,21 +355,150 @@ pub fn encode_config_buf<T: AsRef<[u8]>>(input: T, config: Config, buf: &mut Str
 /// }
 /// ```
 pub fn encode_config_slice<T: AsRef<[u8]>>(input: T, config: Config, output: &mut [u8]) -> usize {
-    let input_bytes = input.as_ref();
+    encode_into(input, config, output)
+}
 
-    let encoded_size = encoded_size(input_bytes.len(), config)
-        .expect("usize overflow when calculating buffer size");
+/// Encode arbitrary octets as base64 into the supplied output buffer, without padding.
+/// Returns the number of bytes written.
+///
+/// This is the lowest level encoding function, for building your own buffering on top of. Padding
+/// is never written, whatever `config` says; only its character set is used. If the input is split
+/// into pieces that are multiples of 3 bytes long, except for the last, the pieces' encodings can
+/// be concatenated, and padding added after the last one if needed (see `padding_len`).
+///
+/// # Panics
+///
+/// If `output` is too small to hold the unpadded encoding of `input`, a panic will result. Use
+/// `encoded_len` with padding turned off to size it.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut buf = [0; 8];
+///     let written = base64::encode_to_slice(b"hello", base64::STANDARD, &mut buf);
+///     assert_eq!(b"aGVsbG8", &buf[..written]);
+/// }
+/// ```
+pub fn encode_to_slice<T: AsRef<[u8]>>(input: T, config: Config, output: &mut [u8]) -> usize {
+    let input = input.as_ref();
+    let encoded_size = encoded_len(input.len(), config.pad(false))
+        .expect("usize overflow when calculating b64 length");
+    assert!(
+        output.len() >= encoded_size,
+        "output is too small to hold the encoded input"
+    );
 
-    let mut b64_output = &mut output[0..encoded_size];
+    encode_with_table(input, output, config.char_set.encode_table())
+}
 
-    encode_with_padding(&input_bytes, config, encoded_size, &mut b64_output);
+/// Encode arbitrary octets as base64 into any `EncodeOutput`: appending to a `String` or
+/// `Vec<u8>`, or writing to the start of a `[u8]`.
+/// Returns the number of bytes written.
+///
+/// `encode_config_buf` and `encode_config_slice` do the same for `String` and `[u8]`.
+///
+/// # Panics
+///
+/// If `output` is a slice too small to hold the encoded version of `input`, a panic will result.
+/// Use `encoded_len` to size it.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut buf = b"data: ".to_vec();
+///     let written = base64::encode_into(b"hello", base64::STANDARD, &mut buf);
+///     assert_eq!(8, written);
+///     assert_eq!(&b"data: aGVsbG8="[..], &buf[..]);
+///
+///     let mut slice = [0_u8; 8];
+///     let _ = base64::encode_into(b"hello", base64::STANDARD, &mut slice[..]);
+///     assert_eq!(b"aGVsbG8=", &slice);
+/// }
+/// ```
+pub fn encode_into<T: AsRef<[u8]>, O: EncodeOutput + ?Sized>(
+    input: T,
+    config: Config,
+    output: &mut O,
+) -> usize {
+    output.write_encoded(input.as_ref(), config)
+}
 
-    encoded_size
+/// Something base64 can be encoded into with `encode_into`.
+///
+/// This is implemented for `String`, `Vec<u8>`, and `[u8]`. It can't be implemented outside this
+/// crate.
+pub trait EncodeOutput: private::Sealed {
+    /// Encode `input`, returning how many bytes were written.
+    fn write_encoded(&mut self, input: &[u8], config: Config) -> usize;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for [u8] {}
+    #[cfg(any(feature = "alloc", feature = "std", test))]
+    impl Sealed for alloc::string::String {}
+    #[cfg(any(feature = "alloc", feature = "std", test))]
+    impl Sealed for alloc::vec::Vec<u8> {}
+}
+
+impl EncodeOutput for [u8] {
+    fn write_encoded(&mut self, input: &[u8], config: Config) -> usize {
+        let encoded_size =
+            encoded_len(input.len(), config).expect("usize overflow when calculating buffer size");
+
+        let b64_output = &mut self[0..encoded_size];
+
+        encode_with_padding(input, config, encoded_size, b64_output);
+
+        encoded_size
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl EncodeOutput for String {
+    fn write_encoded(&mut self, input: &[u8], config: Config) -> usize {
+        let encoded_size = match encoded_len(input.len(), config) {
+            Some(n) => n,
+            None => panic!("integer overflow when calculating buffer size"),
+        };
+        // grow the buffer once up front rather than chunk by chunk
+        self.reserve(encoded_size);
+
+        let mut sink = StringSink::new(self);
+        ChunkedEncoder::new(config)
+            .encode(input, &mut sink)
+            .expect("Writing to a String shouldn't fail");
+
+        encoded_size
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl EncodeOutput for Vec<u8> {
+    fn write_encoded(&mut self, input: &[u8], config: Config) -> usize {
+        let encoded_size = match encoded_len(input.len(), config) {
+            Some(n) => n,
+            None => panic!("integer overflow when calculating buffer size"),
+        };
+        let start = self.len();
+        self.resize(start + encoded_size, 0);
+
+        encode_with_padding(input, config, encoded_size, &mut self[start..]);
+
+        encoded_size
+    }
 }
 
 /// B64-encode and pad (if configured).
 ///
-/// This helper exists to avoid recalculating encoded_size, which is relatively expensive on short
+/// This helper exists to avoid recalculating the encoded length, which is relatively expensive on short
 /// inputs.
 ///
 /// `encoded_size` is the encoded size calculated for `input`.
@@ -140,10 +509,14 @@ pub fn encode_config_slice<T: AsRef<[u8]>>(input: T, config: Config, output: &mu
 fn encode_with_padding(input: &[u8], config: Config, encoded_size: usize, output: &mut [u8]) {
     debug_assert_eq!(encoded_size, output.len());
 
-    let b64_bytes_written = encode_to_slice(input, output, config.char_set.encode_table());
+    let b64_bytes_written = encode_with_table(input, output, config.char_set.encode_table());
 
     let padding_bytes = if config.pad {
-        add_padding(input.len(), &mut output[b64_bytes_written..])
+        add_padding(
+            input.len(),
+            config.pad_byte,
+            &mut output[b64_bytes_written..],
+        )
     } else {
         0
     };
@@ -155,6 +528,7 @@ fn encode_with_padding(input: &[u8], config: Config, encoded_size: usize, output
     debug_assert_eq!(encoded_size, encoded_bytes);
 }
 
+// Big-endian, so the first byte's bits end up most significant and are encoded first
 #[inline]
 fn read_u64(s: &[u8]) -> u64 {
     u64::from_be_bytes(s[..8].try_into().unwrap())
@@ -164,7 +538,7 @@ fn read_u64(s: &[u8]) -> u64 {
 /// `output` must be long enough to hold the encoded `input` without padding.
 /// Returns the number of bytes written.
 #[inline]
-pub fn encode_to_slice(input: &[u8], output: &mut [u8], encode_table: &[u8; 64]) -> usize {
+pub fn encode_with_table(input: &[u8], output: &mut [u8], encode_table: &[u8; 64]) -> usize {
     let mut input_index: usize = 0;
 
     const BLOCKS_PER_FAST_LOOP: usize = 4;
@@ -281,8 +655,22 @@ pub fn encode_to_slice(input: &[u8], output: &mut [u8], encode_table: &[u8; 64])
     output_index
 }
 
-/// calculate the base64 encoded string size, including padding if appropriate
-pub fn encoded_size(bytes_len: usize, config: Config) -> Option<usize> {
+/// Returns the length of the base64 encoding of `bytes_len` bytes with `config`, including padding
+/// if `config` pads, or `None` if that length would overflow `usize`.
+///
+/// This is the exact size `encode_config_slice` needs for its output.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     assert_eq!(Some(8), base64::encoded_len(4, base64::STANDARD));
+///     assert_eq!(Some(6), base64::encoded_len(4, base64::STANDARD_NO_PAD));
+/// }
+/// ```
+pub fn encoded_len(bytes_len: usize, config: Config) -> Option<usize> {
     let rem = bytes_len % 3;
 
     let complete_input_chunks = bytes_len / 3;
@@ -304,15 +692,38 @@ pub fn encoded_size(bytes_len: usize, config: Config) -> Option<usize> {
     }
 }
 
+/// Returns how many padding bytes the base64 encoding of `bytes_len` bytes with `config` ends with:
+/// 0, 1, or 2 if `config` pads, and always 0 if it doesn't.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     assert_eq!(2, base64::padding_len(4, base64::STANDARD));
+///     assert_eq!(0, base64::padding_len(4, base64::STANDARD_NO_PAD));
+///     assert_eq!(0, base64::padding_len(6, base64::STANDARD));
+/// }
+/// ```
+pub fn padding_len(bytes_len: usize, config: Config) -> usize {
+    if config.pad {
+        (3 - bytes_len % 3) % 3
+    } else {
+        0
+    }
+}
+
 /// Write padding characters.
+/// `pad_byte` is the padding character to write.
 /// `output` is the slice where padding should be written, of length at least 2.
 ///
 /// Returns the number of padding bytes written.
-pub fn add_padding(input_len: usize, output: &mut [u8]) -> usize {
+pub fn add_padding(input_len: usize, pad_byte: u8, output: &mut [u8]) -> usize {
     let rem = input_len % 3;
     let mut bytes_written = 0;
     for _ in 0..((3 - rem) % 3) {
-        output[bytes_written] = PAD_BYTE;
+        output[bytes_written] = pad_byte;
         bytes_written += 1;
     }
 
@@ -320,12 +731,13 @@ pub fn add_padding(input_len: usize, output: &mut [u8]) -> usize {
 }
 
 #[cfg(test)]
+#[allow(clippy::single_component_path_imports, clippy::unnecessary_mut_passed)]
 mod tests {
     use super::*;
     use crate::{
         decode::decode_config_buf,
         tests::{assert_encode_sanity, random_config},
-        Config, STANDARD, URL_SAFE_NO_PAD,
+        Config, PAD_BYTE, STANDARD, URL_SAFE_NO_PAD,
     };
 
     use rand::{
@@ -336,7 +748,7 @@ mod tests {
     use std::str;
 
     #[test]
-    fn encoded_size_correct_standard() {
+    fn encoded_len_correct_standard() {
         assert_encoded_length(0, 0, STANDARD);
 
         assert_encoded_length(1, 4, STANDARD);
@@ -361,7 +773,7 @@ mod tests {
     }
 
     #[test]
-    fn encoded_size_correct_no_pad() {
+    fn encoded_len_correct_no_pad() {
         assert_encoded_length(0, 0, URL_SAFE_NO_PAD);
 
         assert_encoded_length(1, 2, URL_SAFE_NO_PAD);
@@ -386,8 +798,103 @@ mod tests {
     }
 
     #[test]
-    fn encoded_size_overflow() {
-        assert_eq!(None, encoded_size(std::usize::MAX, STANDARD));
+    fn encoded_len_overflow() {
+        assert_eq!(None, encoded_len(std::usize::MAX, STANDARD));
+    }
+
+    #[test]
+    fn encode_to_slice_pieces_concatenate() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            let input: Vec<u8> = (0..rng.gen_range(0, 200)).map(|_| rng.gen()).collect();
+            let split = rng.gen_range(0, input.len() / 3 + 1) * 3;
+
+            let mut output = vec![0_u8; encoded_len(input.len(), config).unwrap()];
+            let mut written = encode_to_slice(&input[..split], config, &mut output);
+            written += encode_to_slice(&input[split..], config, &mut output[written..]);
+            if config.pad {
+                written += add_padding(input.len(), config.pad_byte, &mut output[written..]);
+            }
+
+            assert_eq!(encode_config(&input, config).as_bytes(), &output[..written]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "output is too small to hold the encoded input")]
+    fn encode_to_slice_panics_when_output_too_small() {
+        let mut output = [0_u8; 6];
+        let _ = encode_to_slice(b"hello", STANDARD, &mut output);
+    }
+
+    #[test]
+    fn try_encode_config_matches_encode_config() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for input_len in 0..100 {
+            let config = random_config(&mut rng);
+            let input: Vec<u8> = (0..input_len).map(|_| rng.gen()).collect();
+
+            assert_eq!(
+                Ok(encode_config(&input, config)),
+                try_encode_config(&input, config)
+            );
+        }
+
+        assert_eq!(
+            "Encoded length would overflow usize.",
+            EncodeError::LengthOverflow.to_string()
+        );
+    }
+
+    #[test]
+    fn encode_config_has_exact_capacity() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for input_len in 0..100 {
+            let config = random_config(&mut rng);
+            let input = vec![0_u8; input_len];
+            let encoded = encode_config(&input, config);
+
+            assert_eq!(encoded.len(), encoded.capacity());
+            assert_eq!(&encoded[..], &*encode_config_boxed(&input, config));
+        }
+    }
+
+    #[test]
+    fn encode_cow_borrows_only_empty_input() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for input_len in 0..100 {
+            let config = random_config(&mut rng);
+            let input = vec![0_u8; input_len];
+
+            match encode_cow(&input, config) {
+                Cow::Borrowed(encoded) => {
+                    assert!(input.is_empty());
+                    assert_eq!("", encoded);
+                }
+                Cow::Owned(encoded) => assert_eq!(encode_config(&input, config), encoded),
+            }
+        }
+    }
+
+    #[test]
+    fn padding_len_matches_encoding() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for bytes_len in 0..100 {
+            let config = random_config(&mut rng);
+            let encoded = encode_config(vec![0_u8; bytes_len], config);
+            let padding = encoded.bytes().rev().take_while(|&b| b == config.pad_byte);
+
+            assert_eq!(padding.count(), padding_len(bytes_len, config));
+        }
+
+        assert_eq!(0, padding_len(std::usize::MAX, STANDARD));
+        assert_eq!(1, padding_len(std::usize::MAX - 1, STANDARD));
     }
 
     #[test]
@@ -445,6 +952,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encode_config_replace_discards_previous_contents() {
+        let mut buf = String::from("#######");
+        let capacity = buf.capacity();
+
+        encode_config_replace(b"hi", STANDARD, &mut buf);
+        assert_eq!("aGk=", buf);
+        assert_eq!(capacity, buf.capacity());
+
+        encode_config_replace(b"", STANDARD, &mut buf);
+        assert_eq!("", buf);
+    }
+
+    #[test]
+    fn encode_config_into_str_matches_encode_config() {
+        let mut scratch = String::from("#######");
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let input: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+            let config = random_config(&mut rng);
+
+            assert_eq!(
+                encode_config(&input, config),
+                encode_config_into_str(&input, config, &mut scratch)
+            );
+        }
+    }
+
     #[test]
     fn encode_config_slice_into_nonempty_buffer_doesnt_clobber_suffix() {
         let mut orig_data = Vec::new();
@@ -477,7 +1013,7 @@ mod tests {
 
             let config = random_config(&mut rng);
 
-            let encoded_size = encoded_size(input_len, config).unwrap();
+            let encoded_size = encoded_len(input_len, config).unwrap();
 
             assert_eq!(
                 encoded_size,
@@ -523,7 +1059,7 @@ mod tests {
 
             let config = random_config(&mut rng);
 
-            let encoded_size = encoded_size(input_len, config).unwrap();
+            let encoded_size = encoded_len(input_len, config).unwrap();
 
             encoded_data.resize(encoded_size, 0);
 
@@ -565,7 +1101,7 @@ mod tests {
             let config = random_config(&mut rng);
 
             // fill up the output buffer with garbage
-            let encoded_size = encoded_size(input_len, config).unwrap();
+            let encoded_size = encoded_len(input_len, config).unwrap();
             for _ in 0..encoded_size {
                 output.push(rng.gen());
             }
@@ -573,7 +1109,7 @@ mod tests {
             let orig_output_buf = output.to_vec();
 
             let bytes_written =
-                encode_to_slice(&input, &mut output, config.char_set.encode_table());
+                encode_with_table(&input, &mut output, config.char_set.encode_table());
 
             // make sure the part beyond bytes_written is the same garbage it was before
             assert_eq!(orig_output_buf[bytes_written..], output[bytes_written..]);
@@ -605,7 +1141,7 @@ mod tests {
             let config = random_config(&mut rng);
 
             // fill up the output buffer with garbage
-            let encoded_size = encoded_size(input_len, config).unwrap();
+            let encoded_size = encoded_len(input_len, config).unwrap();
             for _ in 0..encoded_size + 1000 {
                 output.push(rng.gen());
             }
@@ -639,7 +1175,7 @@ mod tests {
 
             let orig_output_buf = output.to_vec();
 
-            let bytes_written = add_padding(input_len, &mut output);
+            let bytes_written = add_padding(input_len, PAD_BYTE, &mut output);
 
             // make sure the part beyond bytes_written is the same garbage it was before
             assert_eq!(orig_output_buf[bytes_written..], output[bytes_written..]);
@@ -649,8 +1185,8 @@ mod tests {
         }
     }
 
-    fn assert_encoded_length(input_len: usize, encoded_len: usize, config: Config) {
-        assert_eq!(encoded_len, encoded_size(input_len, config).unwrap());
+    fn assert_encoded_length(input_len: usize, expected_len: usize, config: Config) {
+        assert_eq!(expected_len, encoded_len(input_len, config).unwrap());
 
         let mut bytes: Vec<u8> = Vec::new();
         let mut rng = rand::rngs::SmallRng::from_entropy();
@@ -662,7 +1198,7 @@ mod tests {
         let encoded = encode_config(&bytes, config);
         assert_encode_sanity(&encoded, config, input_len);
 
-        assert_eq!(encoded_len, encoded.len());
+        assert_eq!(expected_len, encoded.len());
     }
 
     #[test]
@@ -672,4 +1208,108 @@ mod tests {
             encode_config(b"\xFB\xFF", crate::STANDARD_NO_PAD).replace("/", ",")
         );
     }
+
+    #[test]
+    fn encoder_reuses_buffer() {
+        let mut encoder = Encoder::new(STANDARD);
+
+        assert_eq!("aGVsbG8gd29ybGQ=", encoder.encode(b"hello world"));
+        let capacity = encoder.buf.capacity();
+
+        // shorter output leaves nothing from the previous call behind
+        assert_eq!("Zg==", encoder.encode(b"f"));
+        assert_eq!(capacity, encoder.buf.capacity());
+        assert_eq!("", encoder.encode(b""));
+
+        encoder.reset();
+        assert_eq!(0, encoder.buf.capacity());
+        assert_eq!("Zm8=", encoder.encode(b"fo"));
+    }
+
+    #[test]
+    fn encode_into_each_output_matches_encode_config() {
+        let mut input = Vec::new();
+        let mut vec_output = Vec::new();
+        let mut string_output = String::new();
+        let mut slice_output = Vec::new();
+        let input_len_range = Uniform::new(0, 1000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            input.clear();
+            vec_output.clear();
+            string_output.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                input.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            let expected = encode_config(&input, config);
+
+            vec_output.extend_from_slice(b"prefix");
+            assert_eq!(expected.len(), encode_into(&input, config, &mut vec_output));
+            assert_eq!(format!("prefix{}", expected).as_bytes(), &vec_output[..]);
+
+            string_output.push_str("prefix");
+            assert_eq!(
+                expected.len(),
+                encode_into(&input, config, &mut string_output)
+            );
+            assert_eq!(format!("prefix{}", expected), string_output);
+
+            // extra room at the end is left alone
+            slice_output.clear();
+            slice_output.resize(expected.len() + 10, b'#');
+            assert_eq!(
+                expected.len(),
+                encode_into(&input, config, &mut slice_output[..])
+            );
+            assert_eq!(expected.as_bytes(), &slice_output[..expected.len()]);
+            assert_eq!(b"##########", &slice_output[expected.len()..]);
+        }
+    }
+
+    #[test]
+    fn encode_config_fmt_matches_encode_config() {
+        let mut input = Vec::new();
+        let mut output = String::new();
+        let input_len_range = Uniform::new(0, 3000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            input.clear();
+            output.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                input.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+
+            output.push_str("prefix");
+            encode_config_fmt(&input, config, &mut output).unwrap();
+            assert_eq!(format!("prefix{}", encode_config(&input, config)), output);
+        }
+    }
+
+    #[test]
+    fn encode_config_fmt_passes_on_write_errors() {
+        struct FailingWriter;
+
+        impl fmt::Write for FailingWriter {
+            fn write_str(&mut self, _s: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        assert_eq!(
+            Err(fmt::Error),
+            encode_config_fmt(b"foo", STANDARD, &mut FailingWriter)
+        );
+        // nothing to write, so no error
+        assert_eq!(Ok(()), encode_config_fmt(b"", STANDARD, &mut FailingWriter));
+    }
 }
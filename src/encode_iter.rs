@@ -0,0 +1,266 @@
+#[cfg(any(feature = "alloc", feature = "std", test))]
+use crate::encode_config;
+use crate::{
+    encode::{add_padding, encode_with_table, encoded_len},
+    Config,
+};
+#[cfg(any(feature = "alloc", feature = "std", test))]
+use alloc::string::String;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+use core::slice;
+use core::{cmp, iter::FusedIterator};
+
+/// Encode arbitrary octets as base64, one `char` at a time.
+///
+/// Nothing is allocated; each group of 3 input bytes is encoded as the iterator reaches it.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let encoded: String = base64::encode_iter(b"hello", base64::STANDARD).collect();
+///     assert_eq!("aGVsbG8=", encoded);
+///
+///     // or anything else that can be built from chars
+///     let upper: String = base64::encode_iter(b"hello", base64::STANDARD)
+///         .map(|c| c.to_ascii_uppercase())
+///         .collect();
+///     assert_eq!("AGVSBG8=", upper);
+/// }
+/// ```
+pub fn encode_iter(input: &[u8], config: Config) -> EncodeIter<'_> {
+    EncodeIter {
+        input,
+        config,
+        encoded: [0; 4],
+        encoded_index: 0,
+        encoded_len: 0,
+    }
+}
+
+/// An iterator over the base64 encoding of some bytes. See `encode_iter`.
+#[derive(Clone, Debug)]
+pub struct EncodeIter<'a> {
+    /// Input that hasn't been encoded yet
+    input: &'a [u8],
+    config: Config,
+    /// The encoding of the most recent group of input, including padding if it was the last
+    encoded: [u8; 4],
+    // index of the next byte of `encoded` to yield
+    encoded_index: usize,
+    // how many bytes of `encoded` are valid
+    encoded_len: usize,
+}
+
+impl<'a> Iterator for EncodeIter<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.encoded_index == self.encoded_len {
+            if self.input.is_empty() {
+                return None;
+            }
+
+            let group_len = cmp::min(3, self.input.len());
+            let (group, rest) = self.input.split_at(group_len);
+
+            self.encoded_len = encode_with_table(
+                group,
+                &mut self.encoded,
+                self.config.char_set.encode_table(),
+            );
+            if self.config.pad && group_len < 3 {
+                self.encoded_len += add_padding(
+                    group_len,
+                    self.config.pad_byte,
+                    &mut self.encoded[self.encoded_len..],
+                );
+            }
+
+            self.encoded_index = 0;
+            self.input = rest;
+        }
+
+        let c = self.encoded[self.encoded_index] as char;
+        self.encoded_index += 1;
+
+        Some(c)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // a slice is at most isize::MAX bytes long, so its encoding always fits in a usize
+        let len = self.encoded_len - self.encoded_index
+            + encoded_len(self.input.len(), self.config)
+                .expect("usize overflow when calculating encoded length");
+
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for EncodeIter<'a> {}
+
+impl<'a> FusedIterator for EncodeIter<'a> {}
+
+/// Encode arbitrary octets as base64, one `String` per `chunk_len` bytes of input.
+///
+/// This is for input too large to encode all at once, e.g. to send it in pieces. Every chunk but
+/// the last encodes exactly `chunk_len` bytes, so concatenating the chunks gives the same result as
+/// `encode_config`. Only the last chunk can have padding. Empty input yields no chunks at all.
+///
+/// # Panics
+///
+/// If `chunk_len` is not a positive multiple of 3. Otherwise a chunk could end partway through a
+/// group of 3 bytes, and the chunks could not be simply concatenated.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let chunks: Vec<String> =
+///         base64::encode_chunks(b"hello world", base64::STANDARD, 6).collect();
+///     assert_eq!(vec!["aGVsbG8g", "d29ybGQ="], chunks);
+///     assert_eq!(base64::encode(b"hello world"), chunks.concat());
+/// }
+/// ```
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub fn encode_chunks(input: &[u8], config: Config, chunk_len: usize) -> EncodeChunks<'_> {
+    assert!(
+        chunk_len > 0 && chunk_len % 3 == 0,
+        "chunk_len must be a positive multiple of 3"
+    );
+
+    EncodeChunks {
+        chunks: input.chunks(chunk_len),
+        config,
+    }
+}
+
+/// An iterator over the base64 encoding of some bytes, a chunk at a time. See `encode_chunks`.
+#[cfg(any(feature = "alloc", feature = "std", test))]
+#[derive(Clone, Debug)]
+pub struct EncodeChunks<'a> {
+    chunks: slice::Chunks<'a, u8>,
+    config: Config,
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl<'a> Iterator for EncodeChunks<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        // all chunks but the last are a multiple of 3 bytes long, so only the last gets padding
+        self.chunks
+            .next()
+            .map(|chunk| encode_config(chunk, self.config))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl<'a> ExactSizeIterator for EncodeChunks<'a> {}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl<'a> FusedIterator for EncodeChunks<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::random_config, STANDARD};
+
+    use rand::{
+        distributions::{Distribution, Uniform},
+        FromEntropy, Rng,
+    };
+
+    #[test]
+    fn encode_iter_matches_encode_config() {
+        let mut input = Vec::new();
+        let input_len_range = Uniform::new(0, 100);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            input.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                input.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            let expected = encode_config(&input, config);
+
+            let mut iter = encode_iter(&input, config);
+            assert_eq!(expected.len(), iter.len());
+
+            // consume part of it to make sure the length stays accurate partway through
+            let prefix_len = rng.gen_range(0, expected.len() + 1);
+            let prefix: String = iter.by_ref().take(prefix_len).collect();
+            assert_eq!(expected.len() - prefix_len, iter.len());
+
+            let rest: String = iter.by_ref().collect();
+            assert_eq!(expected, prefix + &rest);
+            assert_eq!(0, iter.len());
+            assert_eq!(None, iter.next());
+        }
+    }
+
+    #[test]
+    fn encode_iter_pads_last_group() {
+        assert_eq!("Zg==", encode_iter(b"f", STANDARD).collect::<String>());
+        assert_eq!(
+            "Zg",
+            encode_iter(b"f", STANDARD.pad(false)).collect::<String>()
+        );
+        assert_eq!("", encode_iter(b"", STANDARD).collect::<String>());
+    }
+
+    #[test]
+    fn encode_chunks_concatenate_to_encode_config() {
+        let mut input = Vec::new();
+        let input_len_range = Uniform::new(0, 1000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            input.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                input.push(rng.gen());
+            }
+
+            let config = random_config(&mut rng);
+            let chunk_len = rng.gen_range(1, 100) * 3;
+
+            let chunks = encode_chunks(&input, config, chunk_len);
+            assert_eq!((input_len + chunk_len - 1) / chunk_len, chunks.len());
+
+            let chunks: Vec<String> = chunks.collect();
+            assert_eq!(encode_config(&input, config), chunks.concat());
+
+            if let Some((_last, rest)) = chunks.split_last() {
+                for chunk in rest {
+                    assert_eq!(chunk_len / 3 * 4, chunk.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk_len must be a positive multiple of 3")]
+    fn encode_chunks_rejects_chunk_len_not_multiple_of_3() {
+        let _ = encode_chunks(b"foobar", STANDARD, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "chunk_len must be a positive multiple of 3")]
+    fn encode_chunks_rejects_zero_chunk_len() {
+        let _ = encode_chunks(b"foobar", STANDARD, 0);
+    }
+}
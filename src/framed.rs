@@ -0,0 +1,243 @@
+use crate::{decode_config, encode_config, Config, DecodeError};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+#[cfg(any(feature = "std", test))]
+use std::error;
+
+// the length and the CRC, each a little-endian u32
+const HEADER_LEN: usize = 8;
+
+///Encode arbitrary octets as base64, prefixed with their length and CRC-32 so that `decode_framed`
+///can check that they arrive intact.
+///Returns a String.
+///
+///The length and the CRC-32 (the common IEEE one, as used by zlib and Ethernet) are each a
+///little-endian `u32`, and come before the data in a single base64 string.
+///
+///# Panics
+///
+///Panics if the input is longer than `u32::MAX` bytes.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let framed = base64::encode_framed(b"hello", base64::STANDARD);
+///    assert_eq!("BQAAAIamEDZoZWxsbw==", framed);
+///    assert_eq!(b"hello".to_vec(), base64::decode_framed(&framed, base64::STANDARD).unwrap());
+///}
+///```
+pub fn encode_framed<T: AsRef<[u8]> + ?Sized>(input: &T, config: Config) -> String {
+    let input = input.as_ref();
+    assert!(
+        input.len() <= u32::max_value() as usize,
+        "input is too long to frame"
+    );
+
+    let mut framed = Vec::with_capacity(HEADER_LEN + input.len());
+    framed.extend_from_slice(&(input.len() as u32).to_le_bytes());
+    framed.extend_from_slice(&crc32(input).to_le_bytes());
+    framed.extend_from_slice(input);
+
+    encode_config(&framed, config)
+}
+
+///Decode base64 produced by `encode_framed`, checking the length and CRC-32 in its header.
+///Returns a Result containing the data after the header.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    assert_eq!(
+///        Err(base64::FramedError::CrcMismatch { expected: 0x3610_a686, actual: 0x0d7e_864e }),
+///        base64::decode_framed("BQAAAIamEDZoZWxsTw==", base64::STANDARD)
+///    );
+///}
+///```
+pub fn decode_framed<T: AsRef<[u8]> + ?Sized>(
+    input: &T,
+    config: Config,
+) -> Result<Vec<u8>, FramedError> {
+    let mut decoded = decode_config(input, config).map_err(FramedError::Decode)?;
+    if decoded.len() < HEADER_LEN {
+        return Err(FramedError::MissingHeader);
+    }
+
+    let data = &decoded[HEADER_LEN..];
+    let expected_len = read_u32_le(&decoded[..4]) as usize;
+    if expected_len != data.len() {
+        return Err(FramedError::LengthMismatch {
+            expected: expected_len,
+            actual: data.len(),
+        });
+    }
+
+    let expected_crc = read_u32_le(&decoded[4..HEADER_LEN]);
+    let actual_crc = crc32(data);
+    if expected_crc != actual_crc {
+        return Err(FramedError::CrcMismatch {
+            expected: expected_crc,
+            actual: actual_crc,
+        });
+    }
+
+    let _ = decoded.drain(..HEADER_LEN);
+    Ok(decoded)
+}
+
+/// Errors that can occur while decoding with `decode_framed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FramedError {
+    /// The input was not valid base64.
+    Decode(DecodeError),
+    /// The decoded bytes are too short to hold the length and CRC.
+    MissingHeader,
+    /// The length in the header doesn't match the length of the data that follows it.
+    LengthMismatch {
+        /// The length in the header.
+        expected: usize,
+        /// The length of the data.
+        actual: usize,
+    },
+    /// The CRC-32 in the header doesn't match the data, which must have been corrupted.
+    CrcMismatch {
+        /// The CRC-32 in the header.
+        expected: u32,
+        /// The CRC-32 of the data.
+        actual: u32,
+    },
+}
+
+impl fmt::Display for FramedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FramedError::Decode(ref e) => write!(f, "Invalid base64: {}", e),
+            FramedError::MissingHeader => write!(f, "Too short to have a frame header."),
+            FramedError::LengthMismatch { expected, actual } => write!(
+                f,
+                "Frame header has length {}, but {} bytes of data follow it.",
+                expected, actual
+            ),
+            FramedError::CrcMismatch { expected, actual } => write!(
+                f,
+                "Frame header has CRC-32 {:#010x}, but the data has {:#010x}.",
+                expected, actual
+            ),
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for FramedError {
+    fn description(&self) -> &str {
+        match *self {
+            FramedError::Decode(_) => "invalid base64",
+            FramedError::MissingHeader => "missing frame header",
+            FramedError::LengthMismatch { .. } => "frame length mismatch",
+            FramedError::CrcMismatch { .. } => "frame CRC mismatch",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match *self {
+            FramedError::Decode(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn read_u32_le(input: &[u8]) -> u32 {
+    let mut bytes = [0_u8; 4];
+    bytes.copy_from_slice(input);
+    u32::from_le_bytes(bytes)
+}
+
+/// CRC-32 with the reflected IEEE polynomial, a bit at a time. Framing isn't performance
+/// sensitive enough to be worth a table.
+fn crc32(input: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &b in input {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_config, tests::random_config, STANDARD};
+
+    use rand::{FromEntropy, Rng};
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(b""));
+    }
+
+    #[test]
+    fn framed_roundtrip_random_config() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            let data: Vec<u8> = (0..rng.gen_range(0, 1_000)).map(|_| rng.gen()).collect();
+
+            let framed = encode_framed(&data, config);
+            assert_eq!(data, decode_framed(&framed, config).unwrap());
+        }
+    }
+
+    #[test]
+    fn decode_framed_errors() {
+        let framed = |bytes: &[u8]| encode_config(bytes, STANDARD);
+
+        assert_eq!(
+            Err(FramedError::Decode(DecodeError::InvalidByte(2, b'*'))),
+            decode_framed("BQ*A", STANDARD)
+        );
+        assert_eq!(
+            Err(FramedError::MissingHeader),
+            decode_framed(&framed(&[0, 0, 0, 0, 0, 0, 0]), STANDARD)
+        );
+        assert_eq!(
+            Ok(Vec::new()),
+            decode_framed(&framed(&[0, 0, 0, 0, 0, 0, 0, 0]), STANDARD)
+        );
+        assert_eq!(
+            Err(FramedError::LengthMismatch {
+                expected: 1,
+                actual: 0
+            }),
+            decode_framed(&framed(&[1, 0, 0, 0, 0, 0, 0, 0]), STANDARD)
+        );
+
+        // flip a bit of the data
+        let mut bytes = decode_config(encode_framed(b"hello", STANDARD), STANDARD).unwrap();
+        bytes[HEADER_LEN] ^= 1;
+        assert_eq!(
+            Err(FramedError::CrcMismatch {
+                expected: crc32(b"hello"),
+                actual: crc32(b"iello")
+            }),
+            decode_framed(&framed(&bytes), STANDARD)
+        );
+        assert_eq!(
+            "Frame header has length 1, but 0 bytes of data follow it.",
+            FramedError::LengthMismatch {
+                expected: 1,
+                actual: 0
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::{
+    chunked_encoder::{ChunkedEncoder, FmtSink, Sink},
+    encode_iter, Config,
+};
+#[cfg(any(feature = "alloc", feature = "std", test))]
+use alloc::string::String;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Bytes that hash and compare like their base64 encoding, without encoding them up front.
+///
+/// This is for looking up raw tokens in a collection keyed by their encoded `String`s without
+/// allocating a `String` for every lookup. A `Base64Key` is equal to a `str` or `String` holding
+/// its encoding, and hashes the same way as that `str` does with hashers that don't care how the
+/// bytes written to them are split up, like std's `DefaultHasher`. The encoding is fed to the
+/// hasher, or compared, a chunk at a time.
+///
+/// std's `HashMap` can only be probed with a type its keys borrow as, which a `String` can't do
+/// for `Base64Key`. Probing a `HashMap<String, _>` takes a map with a raw entry API, like
+/// `hashbrown`'s, given the key's hash and `==`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::{Hash, Hasher};
+///
+/// fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+///     let mut hasher = DefaultHasher::new();
+///     t.hash(&mut hasher);
+///     hasher.finish()
+/// }
+///
+/// fn main() {
+///     let key = base64::Base64Key::new(b"hello", base64::STANDARD);
+///
+///     assert_eq!(hash("aGVsbG8="), hash(&key));
+///     assert!(key == *"aGVsbG8=");
+///     assert!(key != *"aGVsbG8");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Base64Key<'a> {
+    bytes: &'a [u8],
+    config: Config,
+}
+
+impl<'a> Base64Key<'a> {
+    /// Create a `Base64Key` for `bytes` encoded with the provided config.
+    pub fn new(bytes: &'a [u8], config: Config) -> Base64Key<'a> {
+        Base64Key { bytes, config }
+    }
+
+    /// The bytes this key encodes.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Whether `encoded` is the encoding of this key's bytes.
+    fn eq_encoded(&self, encoded: &[u8]) -> bool {
+        let mut sink = EqSink { expected: encoded };
+        ChunkedEncoder::new(self.config)
+            .encode(self.bytes, &mut sink)
+            .is_ok()
+            && sink.expected.is_empty()
+    }
+}
+
+impl<'a> Hash for Base64Key<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let _ = ChunkedEncoder::new(self.config).encode(self.bytes, &mut HashSink { state });
+        // what `str` writes after its bytes, so that the hashes match
+        state.write_u8(0xff);
+    }
+}
+
+// Keys with different configs may still have the same encoding, so compare the encodings
+impl<'a, 'b> PartialEq<Base64Key<'b>> for Base64Key<'a> {
+    fn eq(&self, other: &Base64Key<'b>) -> bool {
+        encode_iter(self.bytes, self.config).eq(encode_iter(other.bytes, other.config))
+    }
+}
+
+impl<'a> Eq for Base64Key<'a> {}
+
+impl<'a> PartialEq<str> for Base64Key<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_encoded(other.as_bytes())
+    }
+}
+
+impl<'a> PartialEq<Base64Key<'a>> for str {
+    fn eq(&self, other: &Base64Key<'a>) -> bool {
+        other.eq_encoded(self.as_bytes())
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl<'a> PartialEq<String> for Base64Key<'a> {
+    fn eq(&self, other: &String) -> bool {
+        self.eq_encoded(other.as_bytes())
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+impl<'a> PartialEq<Base64Key<'a>> for String {
+    fn eq(&self, other: &Base64Key<'a>) -> bool {
+        other.eq_encoded(self.as_bytes())
+    }
+}
+
+impl<'a> fmt::Display for Base64Key<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        ChunkedEncoder::new(self.config).encode(self.bytes, &mut FmtSink::new(f))
+    }
+}
+
+struct HashSink<'a, H: Hasher> {
+    state: &'a mut H,
+}
+
+impl<'a, H: Hasher> Sink for HashSink<'a, H> {
+    type Error = ();
+
+    fn write_encoded_bytes(&mut self, encoded: &[u8]) -> Result<(), ()> {
+        self.state.write(encoded);
+        Ok(())
+    }
+}
+
+// Compares each chunk against the next part of `expected`, stopping at the first difference
+struct EqSink<'a> {
+    expected: &'a [u8],
+}
+
+impl<'a> Sink for EqSink<'a> {
+    type Error = ();
+
+    fn write_encoded_bytes(&mut self, encoded: &[u8]) -> Result<(), ()> {
+        if !self.expected.starts_with(encoded) {
+            return Err(());
+        }
+
+        self.expected = &self.expected[encoded.len()..];
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_config, tests::random_config, STANDARD, STANDARD_NO_PAD, URL_SAFE};
+
+    use rand::{FromEntropy, Rng};
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        t.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn key_hashes_and_compares_like_encoded_string() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            // long enough to be encoded in several chunks
+            let data: Vec<u8> = (0..rng.gen_range(0, 3_000)).map(|_| rng.gen()).collect();
+            let encoded = encode_config(&data, config);
+            let key = Base64Key::new(&data, config);
+
+            assert_eq!(hash(&encoded), hash(&key));
+            assert!(key == encoded);
+            assert!(encoded == key);
+            assert!(key == *encoded.as_str());
+            assert_eq!(encoded, key.to_string());
+
+            // a prefix or an extension of the encoding isn't equal
+            if !encoded.is_empty() {
+                assert!(key != encoded[..(encoded.len() - 1)]);
+            }
+            assert!(key != encoded.clone() + "A");
+        }
+    }
+
+    #[test]
+    fn keys_compare_by_encoding() {
+        assert_eq!(
+            Base64Key::new(b"hello", STANDARD),
+            Base64Key::new(b"hello", STANDARD)
+        );
+        assert_ne!(
+            Base64Key::new(b"hello", STANDARD),
+            Base64Key::new(b"hello", STANDARD_NO_PAD)
+        );
+        // no + or / in the encoding, so the character sets agree
+        assert_eq!(
+            Base64Key::new(b"hello", STANDARD),
+            Base64Key::new(b"hello", URL_SAFE)
+        );
+        assert_ne!(
+            Base64Key::new(&[0xFF], STANDARD),
+            Base64Key::new(&[0xFF], URL_SAFE)
+        );
+    }
+}
@@ -3,11 +3,17 @@
 //! There isn't just one type of Base64; that would be too simple. You need to choose a character
 //! set (standard, URL-safe, etc) and padding suffix (yes/no).
 //! The `Config` struct encapsulates this info. There are some common configs included: `STANDARD`,
-//! `URL_SAFE`, etc. You can also make your own `Config` if needed.
+//! `URL_SAFE`, etc. You can also make your own `Config` if needed, including one with a
+//! `CharacterSet::Custom` alphabet defined at runtime.
 //!
 //! The functions that don't have `config` in the name (e.g. `encode()` and `decode()`) use the
 //! `STANDARD` config .
 //!
+//! Whatever the config, bits are packed most significant first, as RFC 4648 specifies: the first
+//! symbol is the top 6 bits of the first byte, the second symbol is the remaining 2 bits followed
+//! by the top 4 bits of the second byte, and so on. Variants that pack bits in some other order
+//! aren't supported.
+//!
 //! The functions that write to a slice (the ones that end in `_slice`) are generally the fastest
 //! because they don't need to resize anything. If it fits in your workflow and you care about
 //! performance, keep using the same buffer (growing as need be) and use the `_slice` methods for
@@ -37,6 +43,7 @@
 //! | `decode_config`         | Returns a new `Vec<u8>`       | Always                         |
 //! | `decode_config_buf`     | Appends to provided `Vec<u8>` | Only if `Vec` needs to grow    |
 //! | `decode_config_slice`   | Writes to provided `&[u8]`    | Never                          |
+//! | `decode_in_place`       | Overwrites input `Vec<u8>`    | Never                          |
 //!
 //! Unlike encoding, where all possible input is valid, decoding can fail (see `DecodeError`).
 //!
@@ -55,9 +62,11 @@
 //!
 //! If length calculations result in overflowing `usize`, a panic will result.
 //!
-//! The `_slice` flavors of encode or decode will panic if the provided output slice is too small,
+//! `encode_config_slice` will panic if the provided output slice is too small; use `encoded_len`
+//! to size it. `decode_config_slice` returns `DecodeError::OutputSliceTooSmall` instead; use
+//! `max_decoded_len` to size its output.
 
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::cast_lossless))]
+#![allow(clippy::cast_lossless)]
 #![deny(
     missing_docs,
     trivial_casts,
@@ -76,29 +85,108 @@ extern crate alloc;
 #[cfg(any(feature = "std", test))]
 extern crate std as alloc;
 
+mod alphabet;
+pub use crate::alphabet::{AlphabetError, CustomAlphabet};
+
 mod chunked_encoder;
 pub mod display;
 #[cfg(any(feature = "std", test))]
 pub mod read;
-mod tables;
+pub mod tables;
 #[cfg(any(feature = "std", test))]
 pub mod write;
 
 mod encode;
-pub use crate::encode::encode_config_slice;
 #[cfg(any(feature = "alloc", feature = "std", test))]
-pub use crate::encode::{encode, encode_config, encode_config_buf};
+pub use crate::encode::{
+    encode, encode_config, encode_config_boxed, encode_config_buf, encode_config_into_str,
+    encode_config_replace, encode_cow, try_encode_config, EncodeError, Encoder,
+};
+pub use crate::encode::{
+    encode_config_fmt, encode_config_slice, encode_into, encode_to_slice, encoded_len, padding_len,
+    EncodeOutput,
+};
+
+mod encode_iter;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::encode_iter::{encode_chunks, EncodeChunks};
+pub use crate::encode_iter::{encode_iter, EncodeIter};
+
+mod decode_iter;
+pub use crate::decode_iter::DecodeIter;
+
+mod key;
+pub use crate::key::Base64Key;
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod verbose;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::verbose::{decode_verbose, VerboseDecodeError};
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod multi;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::multi::{decode_multi, DecodeMultiError};
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod framed;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::framed::{decode_framed, encode_framed, FramedError};
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod transcode;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::transcode::transcode;
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod words;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::words::{decode_words, encode_words, Word};
+
+#[cfg(any(feature = "alloc", feature = "std", test))]
+mod stream_decoder;
+#[cfg(any(feature = "alloc", feature = "std", test))]
+pub use crate::stream_decoder::StreamDecoder;
 
 mod decode;
 #[cfg(any(feature = "alloc", feature = "std", test))]
-pub use crate::decode::{decode, decode_config, decode_config_buf};
-pub use crate::decode::{decode_config_slice, DecodeError};
+pub use crate::decode::{
+    decode, decode_config, decode_config_exact, decode_config_str, decode_config_with_info,
+    decode_in_place, decode_iter, DecodeStrError, Decoder,
+};
+pub use crate::decode::{
+    decode_config_buf, decode_config_slice, decode_to_slice, max_decoded_len, DecodeBuffer,
+    DecodeError, DecodeErrorKind, DecodeMetadata, DecodePaddingMode,
+};
 
 #[cfg(test)]
 mod tests;
 
+use core::{fmt, str};
+#[cfg(any(feature = "std", test))]
+use std::error;
+
 /// Available encoding character sets
-#[derive(Clone, Copy, Debug)]
+///
+/// Character sets can be parsed from, and displayed as, their names: `standard`, `url-safe`,
+/// `crypt`, `bcrypt`, `imap-mutf7`, and `binhex`. `url_safe` and `urlsafe` are accepted too. Any
+/// other 64 byte string is parsed as the symbols of a custom alphabet, which is also how
+/// `CharacterSet::Custom` is displayed.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let char_set: base64::CharacterSet = "url-safe".parse().unwrap();
+///     assert_eq!("url-safe", char_set.to_string());
+///
+///     let config = base64::Config::with_charset(char_set);
+///     assert_eq!("_-8=", base64::encode_config(&[0xFF, 0xEF], config));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CharacterSet {
     /// The standard character set (uses `+` and `/`).
     ///
@@ -108,6 +196,12 @@ pub enum CharacterSet {
     ///
     /// See [RFC 3548](https://tools.ietf.org/html/rfc3548#section-4).
     UrlSafe,
+    /// Encodes with the standard character set, but also accepts the URL safe character set's
+    /// symbols when decoding, even mixed in the same input: `-` decodes the same as `+`, and `_`
+    /// the same as `/`.
+    ///
+    /// This is for reading input from sources that might use either.
+    StandardOrUrlSafe,
     /// The `crypt(3)` character set (uses `./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz`).
     ///
     /// Not standardized, but folk wisdom on the net asserts that this alphabet is what crypt uses.
@@ -122,61 +216,332 @@ pub enum CharacterSet {
     ///
     /// See [BinHex 4.0 Definition](http://files.stairways.com/other/binhex-40-specs-info.txt)
     BinHex,
+    /// A character set built at runtime from a user-supplied alphabet.
+    ///
+    /// See `CustomAlphabet`.
+    Custom(CustomAlphabet),
+}
+
+impl str::FromStr for CharacterSet {
+    type Err = ParseCharacterSetError;
+
+    fn from_str(s: &str) -> Result<CharacterSet, ParseCharacterSetError> {
+        match s {
+            "standard" => Ok(CharacterSet::Standard),
+            "url-safe" | "url_safe" | "urlsafe" => Ok(CharacterSet::UrlSafe),
+            "standard-or-url-safe" | "standard_or_url_safe" => Ok(CharacterSet::StandardOrUrlSafe),
+            "crypt" => Ok(CharacterSet::Crypt),
+            "bcrypt" => Ok(CharacterSet::Bcrypt),
+            "imap-mutf7" | "imap_mutf7" => Ok(CharacterSet::ImapMutf7),
+            "binhex" => Ok(CharacterSet::BinHex),
+            _ => {
+                let mut symbols = [0_u8; 64];
+                if s.len() != symbols.len() {
+                    return Err(ParseCharacterSetError(()));
+                }
+                symbols.copy_from_slice(s.as_bytes());
+
+                CustomAlphabet::new(&symbols)
+                    .map(CharacterSet::Custom)
+                    .map_err(|_| ParseCharacterSetError(()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for CharacterSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CharacterSet::Standard => f.write_str("standard"),
+            CharacterSet::UrlSafe => f.write_str("url-safe"),
+            CharacterSet::StandardOrUrlSafe => f.write_str("standard-or-url-safe"),
+            CharacterSet::Crypt => f.write_str("crypt"),
+            CharacterSet::Bcrypt => f.write_str("bcrypt"),
+            CharacterSet::ImapMutf7 => f.write_str("imap-mutf7"),
+            CharacterSet::BinHex => f.write_str("binhex"),
+            CharacterSet::Custom(ref alphabet) => {
+                // all symbols are ASCII, so this can't fail
+                let symbols = str::from_utf8(alphabet.encode_table()).map_err(|_| fmt::Error)?;
+                f.write_str(symbols)
+            }
+        }
+    }
+}
+
+/// The error returned when parsing a `CharacterSet` from a string that's neither the name of a
+/// character set nor a valid custom alphabet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCharacterSetError(());
+
+impl fmt::Display for ParseCharacterSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unknown character set. Expected one of standard, url-safe, standard-or-url-safe, \
+             crypt, bcrypt, imap-mutf7, binhex, or 64 symbols for a custom alphabet."
+        )
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for ParseCharacterSetError {
+    fn description(&self) -> &str {
+        "unknown character set"
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
 }
 
 impl CharacterSet {
-    fn encode_table(self) -> &'static [u8; 64] {
-        match self {
-            CharacterSet::Standard => tables::STANDARD_ENCODE,
+    /// Returns true if `b` is one of the symbols this character set decodes, including any that
+    /// are only accepted when decoding, like `-` and `_` in `StandardOrUrlSafe`.
+    ///
+    /// Padding and whitespace aren't symbols, so they aren't valid bytes by this measure even
+    /// though decoding may allow them in some places.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate base64;
+    ///
+    /// use base64::CharacterSet;
+    ///
+    /// fn main() {
+    ///     assert!(CharacterSet::Standard.is_valid_byte(b'+'));
+    ///     assert!(!CharacterSet::Standard.is_valid_byte(b'-'));
+    ///     assert!(!CharacterSet::Standard.is_valid_byte(b'='));
+    ///     assert!(CharacterSet::StandardOrUrlSafe.is_valid_byte(b'-'));
+    /// }
+    /// ```
+    pub fn is_valid_byte(&self, b: u8) -> bool {
+        match *self {
+            // checking the symbols directly is cheaper than building a whole decode table
+            CharacterSet::Custom(ref alphabet) => {
+                alphabet.encode_table().contains(&b)
+                    || alphabet
+                        .alias_table()
+                        .map_or(false, |aliases| aliases.contains(&b))
+            }
+            _ => self.decode_table(&mut None)[b as usize] != tables::INVALID_VALUE,
+        }
+    }
+
+    fn encode_table(&self) -> &[u8; 64] {
+        match *self {
+            CharacterSet::Standard | CharacterSet::StandardOrUrlSafe => tables::STANDARD_ENCODE,
             CharacterSet::UrlSafe => tables::URL_SAFE_ENCODE,
             CharacterSet::Crypt => tables::CRYPT_ENCODE,
             CharacterSet::Bcrypt => tables::BCRYPT_ENCODE,
             CharacterSet::ImapMutf7 => tables::IMAP_MUTF7_ENCODE,
             CharacterSet::BinHex => tables::BINHEX_ENCODE,
+            CharacterSet::Custom(ref alphabet) => alphabet.encode_table(),
         }
     }
 
-    fn decode_table(self) -> &'static [u8; 256] {
-        match self {
+    /// Symbols that decode to the same values as those in `encode_table`, for character sets that
+    /// accept more than one symbol for some values.
+    fn alias_table(&self) -> Option<&[u8; 64]> {
+        match *self {
+            CharacterSet::StandardOrUrlSafe => Some(tables::URL_SAFE_ENCODE),
+            CharacterSet::Custom(ref alphabet) => alphabet.alias_table(),
+            _ => None,
+        }
+    }
+
+    /// Custom alphabets don't carry a decode table, so theirs is built into `custom_table`, which
+    /// is left alone for the built-in character sets.
+    fn decode_table<'a>(&'a self, custom_table: &'a mut Option<[u8; 256]>) -> &'a [u8; 256] {
+        match *self {
             CharacterSet::Standard => tables::STANDARD_DECODE,
             CharacterSet::UrlSafe => tables::URL_SAFE_DECODE,
+            CharacterSet::StandardOrUrlSafe => tables::STANDARD_OR_URL_SAFE_DECODE,
             CharacterSet::Crypt => tables::CRYPT_DECODE,
             CharacterSet::Bcrypt => tables::BCRYPT_DECODE,
             CharacterSet::ImapMutf7 => tables::IMAP_MUTF7_DECODE,
             CharacterSet::BinHex => tables::BINHEX_DECODE,
+            CharacterSet::Custom(ref alphabet) => {
+                custom_table.get_or_insert_with(|| alphabet.decode_table())
+            }
         }
     }
 }
 
+/// Guess whether `input` was encoded with the standard or the URL-safe character set.
+///
+/// The two differ only in the symbols for 62 and 63: `+` and `/` in `Standard`, `-` and `_` in
+/// `UrlSafe`. If only one pair appears in the input, that character set is returned. If neither
+/// appears there's no telling them apart, and if both do the input fits neither, so `None` is
+/// returned in those cases.
+///
+/// Other bytes aren't checked, so the input may still fail to decode with the character set
+/// returned.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// use base64::CharacterSet;
+///
+/// fn main() {
+///     assert_eq!(Some(CharacterSet::UrlSafe), base64::detect_charset("_-8="));
+///     assert_eq!(Some(CharacterSet::Standard), base64::detect_charset("/+8="));
+///     assert_eq!(None, base64::detect_charset("aGVsbG8="));
+///     assert_eq!(None, base64::detect_charset("/-8="));
+/// }
+/// ```
+pub fn detect_charset<T: AsRef<[u8]> + ?Sized>(input: &T) -> Option<CharacterSet> {
+    let input = input.as_ref();
+    let standard = input.iter().any(|&b| b == b'+' || b == b'/');
+    let url_safe = input.iter().any(|&b| b == b'-' || b == b'_');
+
+    match (standard, url_safe) {
+        (true, false) => Some(CharacterSet::Standard),
+        (false, true) => Some(CharacterSet::UrlSafe),
+        _ => None,
+    }
+}
+
 /// Contains configuration parameters for base64 encoding
-#[derive(Clone, Copy, Debug)]
+///
+/// Start from one of the provided configs like `STANDARD`, or from `Config::with_charset`, and
+/// adjust whatever else is needed with the builder-style setters.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let config = base64::Config::with_charset(base64::CharacterSet::UrlSafe)
+///         .pad(false)
+///         .decode_allow_trailing_bits(true);
+///
+///     assert_eq!("_-8", base64::encode_config(&[0xFF, 0xEF], config));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Config {
     /// Character set to use
     char_set: CharacterSet,
-    /// True to pad output with `=` characters
+    /// True to pad output with `pad_byte` characters
     pad: bool,
+    /// The byte used for padding, `=` unless otherwise configured
+    pad_byte: u8,
     /// True to ignore excess nonzero bits in the last few symbols, otherwise an error is returned.
     decode_allow_trailing_bits: bool,
+    /// True to skip bytes that are neither symbols nor padding, otherwise an error is returned.
+    decode_ignore_invalid: bool,
+    /// True to skip whitespace after the end of the encoded data, otherwise an error is returned.
+    decode_allow_trailing_whitespace: bool,
+    /// True to decode without branching on or indexing by the input's symbols
+    decode_constant_time: bool,
+    /// How strictly to check padding when decoding
+    decode_padding_mode: DecodePaddingMode,
+    /// True to decode `%3D` at the end of the input as padding
+    decode_url_escaped_padding: bool,
+    /// The most bytes decoding may produce, if limited
+    decode_max_len: Option<usize>,
 }
 
 impl Config {
     /// Create a new `Config`.
+    ///
+    /// `Config::with_charset(char_set).pad(pad)` is equivalent, and clearer at the call site.
     pub const fn new(char_set: CharacterSet, pad: bool) -> Config {
         Config {
             char_set,
             pad,
+            pad_byte: PAD_BYTE,
             decode_allow_trailing_bits: false,
+            decode_ignore_invalid: false,
+            decode_allow_trailing_whitespace: false,
+            decode_constant_time: false,
+            decode_padding_mode: DecodePaddingMode::Indifferent,
+            decode_url_escaped_padding: false,
+            decode_max_len: None,
         }
     }
 
-    /// Sets whether to pad output with `=` characters.
+    /// Create a new `Config` for the provided character set, with padding and everything else as
+    /// in `STANDARD`.
+    pub const fn with_charset(char_set: CharacterSet) -> Config {
+        Config::new(char_set, true)
+    }
+
+    /// Create a new `Config` for base64 in URLs: the URL-safe character set, without padding.
+    ///
+    /// Padding is left out because `=` has to be percent-encoded in a URL. This is the same as
+    /// `URL_SAFE_NO_PAD`, and can be adjusted further with the other setters.
+    pub const fn for_url() -> Config {
+        Config::new(CharacterSet::UrlSafe, false)
+    }
+
+    /// Sets the character set, keeping everything else, e.g. to get a URL-safe version of a config
+    /// that's otherwise set up as needed.
+    ///
+    /// # Panics
+    ///
+    /// If the padding byte is one of the symbols in `char_set`, a panic will result, since that
+    /// would make encoded output impossible to decode. Use `try_char_set` to get an error instead.
+    pub fn char_set(self, char_set: CharacterSet) -> Config {
+        match self.try_char_set(char_set) {
+            Ok(config) => config,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Sets the character set like `char_set`, but returns an error rather than panicking if the
+    /// padding byte is one of its symbols.
+    pub fn try_char_set(self, char_set: CharacterSet) -> Result<Config, ConfigError> {
+        Config { char_set, ..self }.try_pad_byte(self.pad_byte)
+    }
+
+    /// Sets whether to pad output with padding characters (`=` by default; see `pad_byte`).
     pub const fn pad(self, pad: bool) -> Config {
         Config { pad, ..self }
     }
 
+    /// Sets the byte used for padding when encoding, and expected as padding when decoding.
+    ///
+    /// # Panics
+    ///
+    /// If `pad_byte` is not ASCII, or is one of the symbols in the configured character set, a
+    /// panic will result, since either would make encoded output impossible to decode. Use
+    /// `try_pad_byte` to get an error instead.
+    pub fn pad_byte(self, pad_byte: u8) -> Config {
+        match self.try_pad_byte(pad_byte) {
+            Ok(config) => config,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Sets the byte used for padding like `pad_byte`, but returns an error rather than panicking if
+    /// it's not ASCII or is one of the symbols in the configured character set.
+    pub fn try_pad_byte(self, pad_byte: u8) -> Result<Config, ConfigError> {
+        if !pad_byte.is_ascii() {
+            return Err(ConfigError::NonAsciiPadByte(pad_byte));
+        }
+
+        let mut custom_decode_table = None;
+        let decode_table = self.char_set.decode_table(&mut custom_decode_table);
+        if decode_table[pad_byte as usize] != tables::INVALID_VALUE {
+            return Err(ConfigError::PadByteInCharacterSet(pad_byte));
+        }
+
+        Ok(Config { pad_byte, ..self })
+    }
+
     /// Sets whether to emit errors for nonzero trailing bits.
     ///
-    /// This is useful when implementing
+    /// They're an error by default, as RFC 4648 section 3.5 allows: `YR==` is rejected with
+    /// `DecodeError::InvalidLastSymbol` rather than decoding to `a` like `YQ==` does. Together with
+    /// `DecodePaddingMode::RequireCanonical`, that leaves only one accepted encoding of any bytes,
+    /// for when encoded values are compared for equality.
+    ///
+    /// Allowing them is useful when implementing
     /// [forgiving-base64 decode](https://infra.spec.whatwg.org/#forgiving-base64-decode).
     pub const fn decode_allow_trailing_bits(self, allow: bool) -> Config {
         Config {
@@ -184,62 +549,257 @@ impl Config {
             ..self
         }
     }
+
+    /// Sets whether to skip bytes that are not in the character set (and aren't padding) when
+    /// decoding, rather than returning `DecodeError::InvalidByte`.
+    ///
+    /// This is useful for pulling base64 out of noisy text, like line-wrapped PEM or log output.
+    /// Decoding input with this set is slower, since it has to be done a byte at a time. Padding is
+    /// still checked as usual, and error offsets still refer to the original input, except with
+    /// `DecoderReader`, where they only count the bytes that weren't skipped.
+    pub const fn decode_ignore_invalid(self, ignore: bool) -> Config {
+        Config {
+            decode_ignore_invalid: ignore,
+            ..self
+        }
+    }
+
+    /// Sets whether to accept whitespace (spaces, tabs, line feeds, form feeds, and carriage
+    /// returns) after the end of the encoded data and its padding when decoding, rather than
+    /// returning `DecodeError::InvalidByte`.
+    ///
+    /// This is for input like `"aGVsbG8=\n"`, read from a file or typed in, that ends with a line
+    /// break. Whitespace anywhere else is still an error. Unlike `decode_ignore_invalid`, this
+    /// doesn't slow decoding down. It has no effect with `DecoderReader`. If the padding byte is
+    /// whitespace, like `b' '`, it's still decoded as padding.
+    pub const fn decode_allow_trailing_whitespace(self, allow: bool) -> Config {
+        Config {
+            decode_allow_trailing_whitespace: allow,
+            ..self
+        }
+    }
+
+    /// Sets whether to decode in a way that takes the same time for any valid input of a given
+    /// length, for decoding secrets like keys or tokens.
+    ///
+    /// Symbols are looked up by comparing against the whole character set rather than indexing a
+    /// table, and the input is checked in full rather than stopping early. This is much slower than
+    /// the usual decoding. The length of the input, how much padding it has, and
+    /// whether it's valid are not hidden; decoding invalid input returns the same error as usual.
+    /// This has no effect if `decode_ignore_invalid` is set.
+    pub const fn decode_constant_time(self, constant_time: bool) -> Config {
+        Config {
+            decode_constant_time: constant_time,
+            ..self
+        }
+    }
+
+    /// Sets how strictly padding is checked when decoding. The default is
+    /// `DecodePaddingMode::Indifferent`.
+    ///
+    /// This has no effect on encoding; use `pad` for that.
+    pub const fn decode_padding_mode(self, mode: DecodePaddingMode) -> Config {
+        Config {
+            decode_padding_mode: mode,
+            ..self
+        }
+    }
+
+    /// Sets whether to decode the padding byte URL-escaped, like `%3D` or `%3d` for `=`, at the end
+    /// of the input as the padding byte itself.
+    ///
+    /// This is for tokens from query strings that were percent-encoded before being passed along.
+    /// Up to two escaped padding bytes are recognized, after any trailing whitespace allowed by
+    /// `decode_allow_trailing_whitespace`, and are then checked like any other padding. An escaped
+    /// padding byte anywhere else is still an error. Character sets with `%` as a symbol, like
+    /// BinHex, can't tell an escape from symbols, so this has no effect with them.
+    ///
+    /// This only applies when decoding a whole slice at once, so it has no effect with
+    /// `decode_iter`, `DecodeIter`, `StreamDecoder`, or `DecoderReader`.
+    pub const fn decode_url_escaped_padding(self, allow: bool) -> Config {
+        Config {
+            decode_url_escaped_padding: allow,
+            ..self
+        }
+    }
+
+    /// Sets the most bytes that decoding may produce. Input that would decode to more than that
+    /// results in `DecodeError::TooLong`, and no more than that is ever allocated for the output.
+    ///
+    /// This is useful when decoding untrusted input. `DecoderReader` doesn't enforce this, since
+    /// it doesn't buffer its output; use `Read::take` to limit how much is read from it instead.
+    pub const fn decode_max_len(self, max_len: Option<usize>) -> Config {
+        Config {
+            decode_max_len: max_len,
+            ..self
+        }
+    }
+}
+
+/// Errors that can occur while configuring a `Config`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The padding byte was not ASCII. The offending byte is provided.
+    NonAsciiPadByte(u8),
+    /// The padding byte is also a symbol in the character set. The offending byte is provided.
+    PadByteInCharacterSet(u8),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::NonAsciiPadByte(byte) => write!(f, "Padding byte {} is not ASCII.", byte),
+            ConfigError::PadByteInCharacterSet(byte) => {
+                write!(f, "Padding byte {} is in the character set.", byte)
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for ConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ConfigError::NonAsciiPadByte(_) => "non-ascii padding byte",
+            ConfigError::PadByteInCharacterSet(_) => "padding byte in character set",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        None
+    }
 }
 
 /// Standard character set with padding.
 pub const STANDARD: Config = Config {
     char_set: CharacterSet::Standard,
     pad: true,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// Standard character set without padding.
 pub const STANDARD_NO_PAD: Config = Config {
     char_set: CharacterSet::Standard,
     pad: false,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// URL-safe character set with padding
 pub const URL_SAFE: Config = Config {
     char_set: CharacterSet::UrlSafe,
     pad: true,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// URL-safe character set without padding
+///
+/// This is usually the one to use in URLs, since `=` has to be percent-encoded there. See also
+/// `Config::for_url`.
 pub const URL_SAFE_NO_PAD: Config = Config {
     char_set: CharacterSet::UrlSafe,
     pad: false,
+    pad_byte: PAD_BYTE,
+    decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
+};
+
+/// URL-safe character set with `.` rather than `=` for padding
+///
+/// Some systems pad this way so that encoded values can go in filenames and URLs as they are. It
+/// is the same as `URL_SAFE.pad_byte(b'.')`.
+pub const URL_SAFE_DOT_PAD: Config = Config {
+    char_set: CharacterSet::UrlSafe,
+    pad: true,
+    pad_byte: b'.',
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// As per `crypt(3)` requirements
 pub const CRYPT: Config = Config {
     char_set: CharacterSet::Crypt,
     pad: false,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// Bcrypt character set
 pub const BCRYPT: Config = Config {
     char_set: CharacterSet::Bcrypt,
     pad: false,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// IMAP modified UTF-7 requirements
 pub const IMAP_MUTF7: Config = Config {
     char_set: CharacterSet::ImapMutf7,
     pad: false,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 /// BinHex character set
 pub const BINHEX: Config = Config {
     char_set: CharacterSet::BinHex,
     pad: false,
+    pad_byte: PAD_BYTE,
     decode_allow_trailing_bits: false,
+    decode_ignore_invalid: false,
+    decode_allow_trailing_whitespace: false,
+    decode_constant_time: false,
+    decode_padding_mode: DecodePaddingMode::Indifferent,
+    decode_url_escaped_padding: false,
+    decode_max_len: None,
 };
 
 const PAD_BYTE: u8 = b'=';
@@ -0,0 +1,185 @@
+use crate::{decode_config, Config, DecodeError};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(any(feature = "std", test))]
+use std::error;
+
+///Decode several base64 documents separated by `separator`, each on its own.
+///Returns a Result containing a Vec with the decoded bytes of each segment, in order.
+///
+///Every occurrence of `separator` ends a segment, so the result always has one more element than
+///there are separators in the input. A segment that's empty, e.g. after a trailing separator,
+///decodes to an empty Vec.
+///
+///Decoding stops at the first segment that fails; the returned `DecodeMultiError` says which
+///segment it was and where it starts in the input.
+///
+///# Panics
+///
+///Panics if `separator` is empty.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let segments = base64::decode_multi("aGVsbG8=\n\nd29ybGQ=", base64::STANDARD, b"\n\n").unwrap();
+///    assert_eq!(vec![b"hello".to_vec(), b"world".to_vec()], segments);
+///
+///    let err = base64::decode_multi("aGVsbG8=--d29y*GQ=", base64::STANDARD, b"--").unwrap_err();
+///    assert_eq!(1, err.segment());
+///    assert_eq!(10, err.segment_start());
+///    assert_eq!(&base64::DecodeError::InvalidByte(4, b'*'), err.error());
+///}
+///```
+pub fn decode_multi<T: AsRef<[u8]> + ?Sized>(
+    input: &T,
+    config: Config,
+    separator: &[u8],
+) -> Result<Vec<Vec<u8>>, DecodeMultiError> {
+    assert!(!separator.is_empty(), "separator must not be empty");
+
+    let input = input.as_ref();
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    let mut i = 0;
+
+    loop {
+        let segment_end = if i + separator.len() > input.len() {
+            // no room for another separator, so the rest is the last segment
+            input.len()
+        } else if &input[i..i + separator.len()] == separator {
+            i
+        } else {
+            i += 1;
+            continue;
+        };
+
+        let segment =
+            decode_config(&input[segment_start..segment_end], config).map_err(|error| {
+                DecodeMultiError {
+                    error,
+                    segment: segments.len(),
+                    segment_start,
+                }
+            })?;
+        segments.push(segment);
+
+        if segment_end == input.len() {
+            return Ok(segments);
+        }
+
+        i = segment_end + separator.len();
+        segment_start = i;
+    }
+}
+
+/// A `DecodeError` from one of the segments given to `decode_multi`, along with which segment it
+/// was.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeMultiError {
+    error: DecodeError,
+    segment: usize,
+    segment_start: usize,
+}
+
+impl DecodeMultiError {
+    /// The error decoding the segment. Offsets in it are relative to the start of the segment.
+    pub fn error(&self) -> &DecodeError {
+        &self.error
+    }
+
+    /// The index of the segment that failed, counting from 0.
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    /// The offset in the input where the segment that failed starts.
+    pub fn segment_start(&self) -> usize {
+        self.segment_start
+    }
+}
+
+impl fmt::Display for DecodeMultiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Segment {} (at offset {}): {}",
+            self.segment, self.segment_start, self.error
+        )
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl error::Error for DecodeMultiError {
+    fn description(&self) -> &str {
+        #[allow(deprecated)]
+        self.error.description()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode_config, tests::random_config};
+
+    use rand::{FromEntropy, Rng};
+
+    #[test]
+    fn decode_multi_splits_on_every_separator() {
+        assert_eq!(
+            vec![Vec::<u8>::new()],
+            decode_multi("", crate::STANDARD, b",").unwrap()
+        );
+        assert_eq!(
+            vec![b"f".to_vec(), vec![], b"fo".to_vec(), vec![]],
+            decode_multi("Zg==,,Zm8=,", crate::STANDARD, b",").unwrap()
+        );
+        // a separator that's cut off at the end of the input is just part of the last segment
+        assert_eq!(
+            Err(DecodeError::InvalidByte(4, b'-')),
+            decode_multi("Zg==-", crate::STANDARD, b"--").map_err(|e| e.error)
+        );
+    }
+
+    #[test]
+    fn decode_multi_reports_failing_segment() {
+        let err = decode_multi("Zg==\r\nZm8=\r\nZm9v\r\nZ", crate::STANDARD, b"\r\n").unwrap_err();
+        assert_eq!(3, err.segment());
+        assert_eq!(18, err.segment_start());
+        assert_eq!(&DecodeError::InvalidLength, err.error());
+        assert_eq!(
+            "Segment 3 (at offset 18): Encoded text cannot have a 6-bit remainder.",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn decode_multi_roundtrip_random_config() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            let segments: Vec<Vec<u8>> = (0..rng.gen_range(1, 10))
+                .map(|_| (0..rng.gen_range(0, 50)).map(|_| rng.gen()).collect())
+                .collect();
+
+            // whitespace is never a symbol or padding, so it can't appear within a segment
+            let encoded: Vec<String> = segments.iter().map(|s| encode_config(s, config)).collect();
+            let joined = encoded.join(" \n");
+
+            assert_eq!(segments, decode_multi(&joined, config, b" \n").unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "separator must not be empty")]
+    fn decode_multi_empty_separator_panics() {
+        let _ = decode_multi("Zg==", crate::STANDARD, b"");
+    }
+}
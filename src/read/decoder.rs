@@ -1,4 +1,4 @@
-use crate::{decode_config_slice, Config, DecodeError};
+use crate::{decode_config_slice, tables, Config, DecodeError};
 use std::io::Read;
 use std::{cmp, fmt, io};
 
@@ -89,7 +89,7 @@ impl<R: io::Read> DecoderReader<R> {
     /// Returns a Result with the number of (decoded) bytes copied.
     fn flush_decoded_buf(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         debug_assert!(self.decoded_len > 0);
-        debug_assert!(buf.len() > 0);
+        debug_assert!(!buf.is_empty());
 
         let copy_len = cmp::min(self.decoded_len, buf.len());
         debug_assert!(copy_len > 0);
@@ -109,18 +109,37 @@ impl<R: io::Read> DecoderReader<R> {
 
     /// Read into the remaining space in the buffer after the current contents.
     /// Must only be called when there is space to read into in the buffer.
-    /// Returns the number of bytes read.
+    /// Returns the number of bytes read, including any skipped because of
+    /// `Config::decode_ignore_invalid`.
     fn read_from_delegate(&mut self) -> io::Result<usize> {
         debug_assert!(self.b64_offset + self.b64_len < BUF_SIZE);
 
-        let read = self
-            .inner
-            .read(&mut self.b64_buffer[self.b64_offset + self.b64_len..])?;
-        self.b64_len += read;
+        let start = self.b64_offset + self.b64_len;
+        let read = self.inner.read(&mut self.b64_buffer[start..])?;
+
+        if self.config.decode_ignore_invalid {
+            // Junk has to be dropped here rather than when decoding, or it would throw off which
+            // symbols end up in which quad.
+            let config = self.config;
+            let mut custom_decode_table = None;
+            let decode_table = config.char_set.decode_table(&mut custom_decode_table);
+
+            let mut kept = 0;
+            for i in start..start + read {
+                let b = self.b64_buffer[i];
+                if b == config.pad_byte || decode_table[b as usize] != tables::INVALID_VALUE {
+                    self.b64_buffer[start + kept] = b;
+                    kept += 1;
+                }
+            }
+            self.b64_len += kept;
+        } else {
+            self.b64_len += read;
+        }
 
         debug_assert!(self.b64_offset + self.b64_len <= BUF_SIZE);
 
-        return Ok(read);
+        Ok(read)
     }
 
     /// Decode the requested number of bytes from the b64 buffer into the provided buffer. It's the
@@ -130,11 +149,16 @@ impl<R: io::Read> DecoderReader<R> {
     fn decode_to_buf(&mut self, num_bytes: usize, buf: &mut [u8]) -> io::Result<usize> {
         debug_assert!(self.b64_len >= num_bytes);
         debug_assert!(self.b64_offset + self.b64_len <= BUF_SIZE);
-        debug_assert!(buf.len() > 0);
+        debug_assert!(!buf.is_empty());
 
+        // decode_max_len is a limit on all of the output, which can't be applied to each chunk, and
+        // whitespace or escaped padding at the end of a chunk may not be at the end of the input
         let decoded = decode_config_slice(
             &self.b64_buffer[self.b64_offset..self.b64_offset + num_bytes],
-            self.config,
+            self.config
+                .decode_max_len(None)
+                .decode_allow_trailing_whitespace(false)
+                .decode_url_escaped_padding(false),
             &mut buf[..],
         )
         .map_err(|e| match e {
@@ -145,6 +169,10 @@ impl<R: io::Read> DecoderReader<R> {
             DecodeError::InvalidLastSymbol(offset, byte) => {
                 DecodeError::InvalidLastSymbol(self.total_b64_decoded + offset, byte)
             }
+            DecodeError::InvalidPadding => DecodeError::InvalidPadding,
+            DecodeError::TooLong => DecodeError::TooLong,
+            DecodeError::UnexpectedLength => DecodeError::UnexpectedLength,
+            DecodeError::OutputSliceTooSmall => DecodeError::OutputSliceTooSmall,
         })
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
@@ -182,7 +210,7 @@ impl<R: Read> Read for DecoderReader<R> {
     /// Any errors emitted by the delegate reader are returned. Decoding errors due to invalid
     /// base64 are also possible, and will have `io::ErrorKind::InvalidData`.
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if buf.len() == 0 {
+        if buf.is_empty() {
             return Ok(0);
         }
 
@@ -1,3 +1,10 @@
+#![allow(
+    clippy::needless_borrows_for_generic_args,
+    clippy::ptr_arg,
+    clippy::redundant_guards,
+    clippy::unused_unit
+)]
+
 use std::io::{self, Read};
 
 use rand::{Rng, RngCore};
@@ -5,8 +12,8 @@ use std::{cmp, iter};
 
 use super::decoder::{DecoderReader, BUF_SIZE};
 use crate::encode::encode_config_buf;
-use crate::tests::random_config;
-use crate::{decode_config_buf, DecodeError, STANDARD};
+use crate::tests::{escape_padding_and_add_whitespace, random_config, random_decode_options};
+use crate::{decode_config, decode_config_buf, DecodeError, DecodePaddingMode, STANDARD};
 
 #[test]
 fn simple() {
@@ -80,6 +87,52 @@ fn trailing_junk() {
     }
 }
 
+#[test]
+fn ignore_invalid_skips_junk_across_reads() {
+    let mut rng = rand::thread_rng();
+    let mut bytes = Vec::new();
+    let mut b64 = String::new();
+    let mut noisy = Vec::new();
+    let mut decoded = Vec::new();
+
+    for _ in 0..1_000 {
+        bytes.clear();
+        b64.clear();
+        noisy.clear();
+        decoded.clear();
+
+        let size = rng.gen_range(0, 3 * BUF_SIZE);
+        bytes.extend(iter::repeat(0).take(size));
+        rng.fill_bytes(&mut bytes[..size]);
+
+        encode_config_buf(&bytes[..], STANDARD, &mut b64);
+
+        // line-wrapped, with some other noise thrown in
+        for (i, &b) in b64.as_bytes().iter().enumerate() {
+            if i % 76 == 0 {
+                noisy.extend_from_slice(b"\r\n");
+            }
+            if rng.gen_bool(0.05) {
+                noisy.push(b' ');
+            }
+            noisy.push(b);
+        }
+
+        let mut wrapped_reader = io::Cursor::new(&noisy[..]);
+        let mut short_reader = RandomShortRead {
+            delegate: &mut wrapped_reader,
+            rng: &mut rng,
+        };
+
+        let mut decoder =
+            DecoderReader::new(&mut short_reader, STANDARD.decode_ignore_invalid(true));
+
+        let decoded_len = decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(size, decoded_len);
+        assert_eq!(&bytes[..], &decoded[..]);
+    }
+}
+
 #[test]
 fn handles_short_read_from_delegate() {
     let mut rng = rand::thread_rng();
@@ -115,6 +168,78 @@ fn handles_short_read_from_delegate() {
     }
 }
 
+#[test]
+fn handles_short_read_with_random_decode_options() {
+    let mut rng = rand::thread_rng();
+    let mut bytes = Vec::new();
+    let mut b64 = String::new();
+    let mut decoded = Vec::new();
+
+    for _ in 0..10_000 {
+        bytes.clear();
+        b64.clear();
+        decoded.clear();
+
+        let size = rng.gen_range(0, 10 * BUF_SIZE);
+        bytes.extend(iter::repeat(0).take(size));
+        rng.fill_bytes(&mut bytes[..size]);
+
+        let config = random_decode_options(random_config(&mut rng), &mut rng);
+        encode_config_buf(&bytes[..], config, &mut b64);
+        // the reader doesn't allow for trailing whitespace or escaped padding
+        let reader_config = config
+            .decode_allow_trailing_whitespace(false)
+            .decode_url_escaped_padding(false);
+        let mut b64_bytes = b64.clone().into_bytes();
+        let valid = escape_padding_and_add_whitespace(&mut b64_bytes, reader_config, &mut rng);
+
+        let mut wrapped_reader = io::Cursor::new(&b64_bytes[..]);
+        let mut short_reader = RandomShortRead {
+            delegate: &mut wrapped_reader,
+            rng: &mut rng,
+        };
+
+        let mut decoder = DecoderReader::new(&mut short_reader, config);
+
+        let missing_padding = !config.pad
+            && size % 3 != 0
+            && config.decode_padding_mode == DecodePaddingMode::RequireCanonical;
+        if valid && !missing_padding {
+            let decoded_len = decoder.read_to_end(&mut decoded).unwrap();
+            assert_eq!(size, decoded_len);
+            assert_eq!(&bytes[..], &decoded[..]);
+            continue;
+        }
+
+        // an error can be reported at a different byte, so only whether there was one is compared
+        let expected = decode_config(&b64_bytes, reader_config);
+        match decoder.read_to_end(&mut decoded) {
+            Ok(_) => assert_eq!(expected, Ok(decoded.clone())),
+            Err(e) => {
+                assert_eq!(io::ErrorKind::InvalidData, e.kind());
+                assert!(expected.is_err());
+            }
+        }
+    }
+}
+
+#[test]
+fn url_escaped_padding_is_not_decoded() {
+    let config = STANDARD.decode_url_escaped_padding(true);
+
+    for input in [&b"AA%3D%3D"[..], b"AAAAAA%3D%3D"].iter() {
+        let mut decoder = DecoderReader::new(*input, config);
+        let mut decoded = Vec::new();
+        let err = decoder.read_to_end(&mut decoded).unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(
+            Some(&DecodeError::InvalidByte(input.len() - 6, b'%')),
+            err.get_ref().and_then(|e| e.downcast_ref())
+        );
+    }
+}
+
 #[test]
 fn read_in_short_increments() {
     let mut rng = rand::thread_rng();
@@ -275,9 +400,9 @@ fn reports_invalid_byte_correctly() {
         let mut bulk_buf = Vec::new();
         let bulk_decode_err = decode_config_buf(&b64_bytes[..], config, &mut bulk_buf).err();
 
-        // it's tricky to predict where the invalid data's offset will be since if it's in the last
-        // chunk it will be reported at the first padding location because it's treated as invalid
-        // padding. So, we just check that it's the same as it is for decoding all at once.
+        // it's tricky to predict what the error will be since if it's in the last chunk before
+        // padding it will be reported as invalid padding. So, we just check that it's the same as
+        // it is for decoding all at once.
         assert_eq!(
             bulk_decode_err.map(|e| (e, io::ErrorKind::InvalidData)),
             read_decode_err
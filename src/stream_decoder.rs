@@ -0,0 +1,313 @@
+use crate::{decode::BytewiseDecoder, decode_config_slice, Config, DecodeError};
+use alloc::vec::Vec;
+
+/// Decodes base64 that's pushed to it a piece at a time, such as base64 embedded in a larger
+/// stream that's being parsed as it arrives.
+///
+/// Input can be split anywhere, even partway through a quad; the symbols that don't yet make up
+/// a whole quad are kept until the next call to `feed`. Error offsets are counted from the start
+/// of all the input fed so far. This is the push-based counterpart to `read::DecoderReader`.
+///
+/// Like `DecoderReader`, this doesn't enforce `Config::decode_max_len`. After an error, the
+/// decoder shouldn't be used further.
+///
+/// A clone has all the state of the original, including any part of a quad it's holding on to, so
+/// cloning is a way to save a point to come back to, e.g. to decode ahead speculatively.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate base64;
+///
+/// fn main() {
+///     let mut decoder = base64::StreamDecoder::new(base64::STANDARD);
+///
+///     let mut decoded = decoder.feed(b"aGVsbG8g").unwrap();
+///     decoded.extend(decoder.feed(b"d29y").unwrap());
+///     decoded.extend(decoder.feed(b"bGQ=").unwrap());
+///     decoded.extend(decoder.finish().unwrap());
+///     assert_eq!(b"hello world", &decoded[..]);
+///
+///     let mut decoder = base64::StreamDecoder::new(base64::STANDARD);
+///     assert!(decoder.feed(b"aGVs").is_ok());
+///     assert_eq!(
+///         Err(base64::DecodeError::InvalidByte(5, b'*')),
+///         decoder.feed(b"b*8=")
+///     );
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamDecoder {
+    config: Config,
+    // only used for custom alphabets, built the first time it's needed
+    custom_decode_table: Option<[u8; 256]>,
+    state: BytewiseDecoder,
+}
+
+impl StreamDecoder {
+    /// Create a new decoder that will decode with the provided config.
+    pub fn new(config: Config) -> StreamDecoder {
+        StreamDecoder {
+            config,
+            custom_decode_table: None,
+            state: BytewiseDecoder::default(),
+        }
+    }
+
+    /// Decode the next piece of input. Returns the bytes decoded from all the quads that are now
+    /// complete.
+    pub fn feed(&mut self, input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let mut decoded = Vec::with_capacity((input.len() / 4 + 1) * 3);
+        let mut input = input;
+
+        // Whole quads can be decoded the usual, faster way, up until the last quad in the input,
+        // which could be the end of the encoded data and have padding or trailing whitespace. If
+        // bytes are being skipped, though, there's no telling where quads start and end.
+        if self.state.is_between_quads() && !self.config.decode_ignore_invalid {
+            let allow_whitespace = self.config.decode_allow_trailing_whitespace;
+            let before_end = input
+                .iter()
+                .position(|&b| {
+                    b == self.config.pad_byte || (allow_whitespace && b.is_ascii_whitespace())
+                })
+                .unwrap_or(input.len());
+            let fast_len = before_end.saturating_sub(1) / 4 * 4;
+
+            decoded.resize(fast_len / 4 * 3, 0);
+            // the end of this piece isn't necessarily the end of the input, so nothing that's only
+            // allowed there is allowed here
+            let decoded_len = decode_config_slice(
+                &input[..fast_len],
+                self.config
+                    .decode_max_len(None)
+                    .decode_allow_trailing_whitespace(false)
+                    .decode_url_escaped_padding(false),
+                &mut decoded,
+            )
+            .map_err(|e| match e {
+                DecodeError::InvalidByte(offset, byte) => {
+                    DecodeError::InvalidByte(self.state.offset() + offset, byte)
+                }
+                _ => e,
+            })?;
+            debug_assert_eq!(decoded.len(), decoded_len);
+
+            self.state.skip(fast_len);
+            input = &input[fast_len..];
+        }
+
+        let decode_table = self
+            .config
+            .char_set
+            .decode_table(&mut self.custom_decode_table);
+        self.state
+            .feed(input.iter().cloned(), self.config, decode_table, |bytes| {
+                decoded.extend_from_slice(bytes);
+                Ok(())
+            })?;
+
+        Ok(decoded)
+    }
+
+    /// Check that the input ended properly. Returns any bytes decoded from a final quad that was
+    /// incomplete, as when the input isn't padded.
+    pub fn finish(self) -> Result<Vec<u8>, DecodeError> {
+        let mut decoded = Vec::new();
+
+        let _ = self.state.finish(self.config, |bytes| {
+            decoded.extend_from_slice(bytes);
+            Ok(())
+        })?;
+
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_config, encode_config_buf,
+        tests::{escape_padding_and_add_whitespace, random_config, random_decode_options},
+        DecodePaddingMode, STANDARD,
+    };
+
+    use rand::{
+        distributions::{Distribution, Uniform},
+        FromEntropy, Rng,
+    };
+
+    #[test]
+    fn stream_decode_in_random_pieces_matches_decode_config() {
+        let mut orig_data = Vec::new();
+        let mut encoded_data = String::new();
+        let input_len_range = Uniform::new(0, 1000);
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..10_000 {
+            orig_data.clear();
+            encoded_data.clear();
+
+            let input_len = input_len_range.sample(&mut rng);
+            for _ in 0..input_len {
+                orig_data.push(rng.gen());
+            }
+
+            let config = random_decode_options(random_config(&mut rng), &mut rng);
+            encode_config_buf(&orig_data, config, &mut encoded_data);
+            if config.decode_ignore_invalid && !encoded_data.is_empty() {
+                for _ in 0..rng.gen_range(0, 10) {
+                    let index = rng.gen_range(0, encoded_data.len());
+                    encoded_data.insert(index, '\n');
+                }
+            }
+            let mut encoded_bytes = encoded_data.clone().into_bytes();
+            let valid = escape_padding_and_add_whitespace(
+                &mut encoded_bytes,
+                config.decode_url_escaped_padding(false),
+                &mut rng,
+            );
+
+            // escaped padding is only recognized when decoding all at once
+            let expected = decode_config(&encoded_bytes, config.decode_url_escaped_padding(false));
+            let decoded = stream_decode_in_random_pieces(&encoded_bytes, config, &mut rng);
+            let missing_padding = !config.pad
+                && input_len % 3 != 0
+                && config.decode_padding_mode == DecodePaddingMode::RequireCanonical;
+            if valid && !missing_padding {
+                assert_eq!(Ok(&orig_data), decoded.as_ref());
+                assert_eq!(expected, decoded);
+            } else {
+                // an error can be reported at a different byte, so only whether there was one is
+                // compared
+                assert_eq!(expected.ok(), decoded.ok());
+            }
+        }
+    }
+
+    fn stream_decode_in_random_pieces<R: Rng>(
+        input: &[u8],
+        config: Config,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let mut decoder = StreamDecoder::new(config);
+        let mut decoded = Vec::new();
+        let mut remaining = input;
+        while !remaining.is_empty() {
+            let piece_len = rng.gen_range(0, remaining.len() + 1);
+            let (piece, rest) = remaining.split_at(piece_len);
+            decoded.extend(decoder.feed(piece)?);
+            remaining = rest;
+        }
+        decoded.extend(decoder.finish()?);
+
+        Ok(decoded)
+    }
+
+    #[test]
+    fn stream_decode_error_offsets_count_earlier_pieces() {
+        // in a whole quad
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJj"));
+        assert_eq!(
+            Err(DecodeError::InvalidByte(8, b'*')),
+            decoder.feed(b"ZGVm*GhpamtsbW5v")
+        );
+
+        // in a quad split across pieces
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"YW"));
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"J"));
+        assert_eq!(Err(DecodeError::InvalidByte(3, b'\n')), decoder.feed(b"\n"));
+
+        // in the last quad
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(b"abcdef".to_vec()), decoder.feed(b"YWJjZGVm"));
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"ZR"));
+        assert_eq!(
+            Err(DecodeError::InvalidLastSymbol(9, b'R')),
+            decoder.finish()
+        );
+    }
+
+    #[test]
+    fn stream_decode_checks_padding_and_length_across_pieces() {
+        // the last quad is held back in case it's padded
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZA=="));
+        assert_eq!(Err(DecodeError::InvalidPadding), decoder.feed(b"YWJj"));
+
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZ"));
+        assert_eq!(Err(DecodeError::InvalidLength), decoder.finish());
+
+        let mut decoder =
+            StreamDecoder::new(STANDARD.decode_padding_mode(DecodePaddingMode::RequireCanonical));
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZA="));
+        assert_eq!(Err(DecodeError::InvalidPadding), decoder.finish());
+
+        let mut decoder =
+            StreamDecoder::new(STANDARD.decode_padding_mode(DecodePaddingMode::IgnoreTrailing));
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZA=="));
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"=="));
+        assert_eq!(Ok(b"d".to_vec()), decoder.finish());
+    }
+
+    #[test]
+    fn stream_decode_allows_trailing_whitespace_across_pieces() {
+        let config = STANDARD.decode_allow_trailing_whitespace(true);
+
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZA==\r"));
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"\n"));
+        assert_eq!(Ok(b"d".to_vec()), decoder.finish());
+
+        // more whitespace than the fast path leaves for the last quad
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZA\r\n \t"));
+        assert_eq!(Ok(b"d".to_vec()), decoder.finish());
+
+        // whitespace at the end of one piece that's followed by more symbols in the next
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(Ok(b"abcdef".to_vec()), decoder.feed(b"YWJjZGVm\n"));
+        assert_eq!(
+            Err(DecodeError::InvalidByte(8, b'\n')),
+            decoder.feed(b"YWJj")
+        );
+
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZG\n"));
+        assert_eq!(Err(DecodeError::InvalidByte(6, b'\n')), decoder.feed(b"Vm"));
+    }
+
+    #[test]
+    fn stream_decode_ignores_url_escaped_padding() {
+        let config = STANDARD.decode_url_escaped_padding(true);
+
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'%')),
+            decoder.feed(b"AA%3D%3DAAAA")
+        );
+
+        let mut decoder = StreamDecoder::new(config);
+        assert_eq!(Ok(Vec::new()), decoder.feed(b"Zg"));
+        assert_eq!(
+            Err(DecodeError::InvalidByte(2, b'%')),
+            decoder.feed(b"%3D%3D")
+        );
+    }
+
+    #[test]
+    fn stream_decode_clone_continues_from_same_point() {
+        let mut decoder = StreamDecoder::new(STANDARD);
+        assert_eq!(Ok(b"abc".to_vec()), decoder.feed(b"YWJjZG"));
+
+        let snapshot = decoder.clone();
+        assert_eq!(Err(DecodeError::InvalidByte(6, b'*')), decoder.feed(b"*"));
+
+        let mut decoder = snapshot;
+        assert_eq!(Ok(b"def".to_vec()), decoder.feed(b"Vm"));
+        assert_eq!(Ok(Vec::new()), decoder.finish());
+    }
+}
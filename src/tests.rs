@@ -1,6 +1,10 @@
-use crate::{decode_config, encode::encoded_size, encode_config_buf, CharacterSet, Config};
+use crate::{
+    decode_config, detect_charset, encode::encoded_len, encode_config, encode_config_buf, tables,
+    CharacterSet, Config, ConfigError, CustomAlphabet, DecodeError, DecodePaddingMode,
+    ParseCharacterSetError, STANDARD, URL_SAFE, URL_SAFE_DOT_PAD, URL_SAFE_NO_PAD,
+};
 
-use std::str;
+use std::{collections::HashSet, str};
 
 use rand::{
     distributions::{Distribution, Uniform},
@@ -19,6 +23,438 @@ fn roundtrip_random_config_long() {
     roundtrip_random_config(Uniform::new(0, 1000), 10_000);
 }
 
+#[test]
+fn roundtrip_random_config_with_custom_alphabet() {
+    let mut input_buf: Vec<u8> = Vec::new();
+    let mut encoded_buf = String::new();
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+
+    for _ in 0..10_000 {
+        input_buf.clear();
+        encoded_buf.clear();
+
+        let input_len = rng.gen_range(0, 100);
+        let config = random_config_with_custom_alphabet(&mut rng);
+
+        for _ in 0..input_len {
+            input_buf.push(rng.gen());
+        }
+
+        encode_config_buf(&input_buf, config, &mut encoded_buf);
+
+        assert_encode_sanity(&encoded_buf, config, input_len);
+
+        assert_eq!(input_buf, decode_config(&encoded_buf, config).unwrap());
+    }
+}
+
+#[test]
+fn roundtrip_custom_pad_byte() {
+    let config = STANDARD.pad_byte(b'.');
+
+    assert_eq!("Zg..", encode_config(b"f", config));
+    assert_eq!("Zm8.", encode_config(b"fo", config));
+    assert_eq!(b"f".to_vec(), decode_config("Zg..", config).unwrap());
+    assert_eq!(b"fo".to_vec(), decode_config("Zm8.", config).unwrap());
+    // `=` is no longer padding, so it's just an invalid byte
+    assert_eq!(
+        Err(DecodeError::InvalidByte(3, b'=')),
+        decode_config("Zm8=", config)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Padding byte 43 is in the character set")]
+fn pad_byte_in_character_set_panics() {
+    let _ = STANDARD.pad_byte(b'+');
+}
+
+#[test]
+#[should_panic(expected = "Padding byte 200 is not ASCII")]
+fn pad_byte_not_ascii_panics() {
+    let _ = STANDARD.pad_byte(200);
+}
+
+#[test]
+fn bits_are_packed_most_significant_first() {
+    let symbols = STANDARD.char_set.encode_table();
+
+    // a single set bit anywhere in 3 bytes, repeated so it goes through the fast loops too
+    for bit in 0..24 {
+        let group = (1_u32 << (23 - bit)).to_be_bytes();
+        let input: Vec<u8> = group[1..].iter().cycle().take(60).cloned().collect();
+
+        let mut expected_group = [b'A'; 4];
+        expected_group[bit / 6] = symbols[1 << (5 - bit % 6)];
+        let expected: Vec<u8> = expected_group.iter().cycle().take(80).cloned().collect();
+
+        assert_eq!(&expected[..], encode_config(&input, STANDARD).as_bytes());
+        assert_eq!(input, decode_config(&expected, STANDARD).unwrap());
+    }
+}
+
+#[test]
+fn try_pad_byte_returns_errors() {
+    assert_eq!(Ok(STANDARD.pad_byte(b'.')), STANDARD.try_pad_byte(b'.'));
+    assert_eq!(
+        Err(ConfigError::PadByteInCharacterSet(b'+')),
+        STANDARD.try_pad_byte(b'+')
+    );
+    assert_eq!(Ok(URL_SAFE.pad_byte(b'+')), URL_SAFE.try_pad_byte(b'+'));
+    assert_eq!(
+        Err(ConfigError::NonAsciiPadByte(200)),
+        STANDARD.try_pad_byte(200)
+    );
+}
+
+#[test]
+fn char_set_keeps_other_settings() {
+    let config = STANDARD
+        .pad(false)
+        .pad_byte(b'.')
+        .decode_ignore_invalid(true)
+        .decode_max_len(Some(10));
+    let url_safe = config.char_set(CharacterSet::UrlSafe);
+
+    assert_eq!(
+        Config {
+            char_set: CharacterSet::UrlSafe,
+            ..config
+        },
+        url_safe
+    );
+    assert_eq!("_w", encode_config([0xFF], url_safe));
+    assert_eq!(Ok(url_safe), config.try_char_set(CharacterSet::UrlSafe));
+
+    // `.` is a symbol in crypt's alphabet
+    assert_eq!(
+        Err(ConfigError::PadByteInCharacterSet(b'.')),
+        config.try_char_set(CharacterSet::Crypt)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Padding byte 46 is in the character set")]
+fn char_set_containing_pad_byte_panics() {
+    let _ = URL_SAFE_DOT_PAD.char_set(CharacterSet::Crypt);
+}
+
+#[test]
+fn setters_change_only_their_setting() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let padding_modes = [
+        DecodePaddingMode::Indifferent,
+        DecodePaddingMode::RequireCanonical,
+        DecodePaddingMode::IgnoreTrailing,
+    ];
+
+    for _ in 0..1_000 {
+        let config = random_config(&mut rng)
+            .decode_allow_trailing_bits(rng.gen())
+            .decode_ignore_invalid(rng.gen())
+            .decode_allow_trailing_whitespace(rng.gen())
+            .decode_constant_time(rng.gen())
+            .decode_padding_mode(*padding_modes.choose(&mut rng).unwrap())
+            .decode_url_escaped_padding(rng.gen())
+            .decode_max_len(Some(rng.gen()));
+        let char_set = random_config(&mut rng).char_set;
+        let b: bool = rng.gen();
+
+        if let Ok(changed) = config.try_char_set(char_set) {
+            assert_eq!(Config { char_set, ..config }, changed);
+        }
+        assert_eq!(Config { pad: b, ..config }, config.pad(b));
+        // the padding byte is already known not to be a symbol
+        assert_eq!(config, config.pad_byte(config.pad_byte));
+        assert_eq!(
+            Config {
+                decode_allow_trailing_bits: b,
+                ..config
+            },
+            config.decode_allow_trailing_bits(b)
+        );
+        assert_eq!(
+            Config {
+                decode_ignore_invalid: b,
+                ..config
+            },
+            config.decode_ignore_invalid(b)
+        );
+        assert_eq!(
+            Config {
+                decode_allow_trailing_whitespace: b,
+                ..config
+            },
+            config.decode_allow_trailing_whitespace(b)
+        );
+        assert_eq!(
+            Config {
+                decode_constant_time: b,
+                ..config
+            },
+            config.decode_constant_time(b)
+        );
+        let mode = *padding_modes.choose(&mut rng).unwrap();
+        assert_eq!(
+            Config {
+                decode_padding_mode: mode,
+                ..config
+            },
+            config.decode_padding_mode(mode)
+        );
+        assert_eq!(
+            Config {
+                decode_url_escaped_padding: b,
+                ..config
+            },
+            config.decode_url_escaped_padding(b)
+        );
+        assert_eq!(
+            Config {
+                decode_max_len: None,
+                ..config
+            },
+            config.decode_max_len(None)
+        );
+    }
+}
+
+#[test]
+fn character_set_display_roundtrips_through_from_str() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let char_sets = [
+        CharacterSet::Standard,
+        CharacterSet::UrlSafe,
+        CharacterSet::StandardOrUrlSafe,
+        CharacterSet::Crypt,
+        CharacterSet::Bcrypt,
+        CharacterSet::ImapMutf7,
+        CharacterSet::BinHex,
+        CharacterSet::Custom(random_alphabet(&mut rng)),
+    ];
+
+    for char_set in char_sets.iter() {
+        let parsed: CharacterSet = char_set.to_string().parse().unwrap();
+        assert_eq!(char_set.encode_table(), parsed.encode_table());
+    }
+}
+
+#[test]
+fn standard_or_url_safe_decodes_either_alphabet() {
+    let config = Config::new(CharacterSet::StandardOrUrlSafe, true);
+    let input = [0xFB, 0xEF, 0xBE, 0xFF, 0xFF, 0xFF];
+
+    // encoding is always standard
+    assert_eq!("++++////", encode_config(input, config));
+
+    for &encoded in &["++++////", "----____", "+-+-/_/_", "-+-+_/_/"] {
+        assert_eq!(input.to_vec(), decode_config(encoded, config).unwrap());
+        assert_eq!(
+            input.to_vec(),
+            decode_config(encoded, config.decode_constant_time(true)).unwrap()
+        );
+        assert_eq!(
+            input.to_vec(),
+            decode_config(encoded, config.decode_ignore_invalid(true)).unwrap()
+        );
+    }
+
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    for _ in 0..1_000 {
+        let input: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+        let url_safe = encode_config(&input, URL_SAFE);
+        assert_eq!(input, decode_config(&url_safe, config).unwrap());
+    }
+
+    assert_eq!(
+        Err(DecodeError::InvalidByte(1, b'.')),
+        decode_config("+.+-", config)
+    );
+    assert_eq!(
+        Err(ConfigError::PadByteInCharacterSet(b'_')),
+        config.try_pad_byte(b'_')
+    );
+}
+
+#[test]
+fn is_valid_byte_matches_decoding() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let alphabet = random_alphabet(&mut rng);
+    let mut aliases = *alphabet.encode_table();
+    aliases[0] = (b'!'..=b'~')
+        .find(|b| *b != b'=' && !aliases.contains(b))
+        .unwrap();
+    let char_sets = [
+        CharacterSet::Standard,
+        CharacterSet::UrlSafe,
+        CharacterSet::StandardOrUrlSafe,
+        CharacterSet::Crypt,
+        CharacterSet::Bcrypt,
+        CharacterSet::ImapMutf7,
+        CharacterSet::BinHex,
+        CharacterSet::Custom(alphabet),
+        CharacterSet::Custom(
+            CustomAlphabet::with_aliases(alphabet.encode_table(), &aliases).unwrap(),
+        ),
+    ];
+
+    for &char_set in char_sets.iter() {
+        // padding is off, so only symbols decode on their own
+        let config = Config::new(char_set, false).decode_allow_trailing_bits(true);
+        for b in 0..=255_u8 {
+            assert_eq!(
+                decode_config([b, b], config).is_ok(),
+                char_set.is_valid_byte(b),
+                "{} in {}",
+                b,
+                char_set
+            );
+        }
+    }
+}
+
+#[test]
+fn decode_tables_invert_encode_tables() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let char_sets = [
+        CharacterSet::Standard,
+        CharacterSet::UrlSafe,
+        CharacterSet::StandardOrUrlSafe,
+        CharacterSet::Crypt,
+        CharacterSet::Bcrypt,
+        CharacterSet::ImapMutf7,
+        CharacterSet::BinHex,
+        CharacterSet::Custom(random_alphabet(&mut rng)),
+    ];
+
+    for &char_set in char_sets.iter() {
+        let mut expected = [tables::INVALID_VALUE; 256];
+        for (value, &symbol) in char_set.encode_table().iter().enumerate() {
+            expected[symbol as usize] = value as u8;
+        }
+        if let Some(aliases) = char_set.alias_table() {
+            for (value, &symbol) in aliases.iter().enumerate() {
+                expected[symbol as usize] = value as u8;
+            }
+        }
+
+        assert_eq!(
+            &expected[..],
+            &char_set.decode_table(&mut None)[..],
+            "{}",
+            char_set
+        );
+    }
+}
+
+#[test]
+fn character_set_from_str_accepts_aliases() {
+    for &name in &["url-safe", "url_safe", "urlsafe"] {
+        let char_set: CharacterSet = name.parse().unwrap();
+        assert_eq!("url-safe", char_set.to_string());
+    }
+}
+
+#[test]
+fn character_set_from_str_rejects_unknown_names() {
+    for &name in &["", "Standard", "url safe", "base64"] {
+        assert_eq!(
+            Err(ParseCharacterSetError(())),
+            name.parse::<CharacterSet>().map(|_| ())
+        );
+    }
+
+    // 64 bytes, but not a valid alphabet
+    let duplicated = "A".repeat(64);
+    assert!(duplicated.parse::<CharacterSet>().is_err());
+}
+
+#[test]
+fn detect_charset_of_encoded_random_bytes() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+
+    for _ in 0..1_000 {
+        let input: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+
+        for &char_set in &[CharacterSet::Standard, CharacterSet::UrlSafe] {
+            let encoded = encode_config(&input, Config::new(char_set, true));
+
+            // random input usually has at least one of the last two symbols, but not always
+            match detect_charset(&encoded) {
+                Some(detected) => assert_eq!(char_set, detected),
+                None => assert!(!encoded.contains(|c| "+/-_".contains(c))),
+            }
+        }
+    }
+}
+
+#[test]
+fn detect_charset_rejects_mixed_input() {
+    assert_eq!(None, detect_charset(""));
+    assert_eq!(None, detect_charset("a+b_"));
+    assert_eq!(None, detect_charset("-/"));
+    assert_eq!(Some(CharacterSet::Standard), detect_charset(b"a+b*"));
+}
+
+#[test]
+fn for_url_is_url_safe_no_pad() {
+    assert_eq!(URL_SAFE_NO_PAD, Config::for_url());
+    assert_eq!("_-8", encode_config([0xFF, 0xEF], Config::for_url()));
+    assert_eq!(
+        Ok(vec![0xFF, 0xEF]),
+        decode_config("_-8", Config::for_url())
+    );
+}
+
+#[test]
+fn roundtrip_url_safe_dot_pad() {
+    assert_eq!(URL_SAFE.pad_byte(b'.'), URL_SAFE_DOT_PAD);
+    assert_eq!("_-8.", encode_config([0xFF, 0xEF], URL_SAFE_DOT_PAD));
+    assert_eq!("_w..", encode_config([0xFF], URL_SAFE_DOT_PAD));
+    assert_eq!(Ok(vec![0xFF]), decode_config("_w..", URL_SAFE_DOT_PAD));
+    assert_eq!(
+        Err(DecodeError::InvalidByte(2, b'=')),
+        decode_config("_w==", URL_SAFE_DOT_PAD)
+    );
+
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    for _ in 0..1_000 {
+        let data: Vec<u8> = (0..rng.gen_range(0, 100)).map(|_| rng.gen()).collect();
+        let encoded = encode_config(&data, URL_SAFE_DOT_PAD);
+
+        assert!(!encoded.contains('='));
+        assert_eq!(data, decode_config(&encoded, URL_SAFE_DOT_PAD).unwrap());
+    }
+}
+
+#[test]
+fn configs_compare_and_hash_by_value() {
+    let mut rng = rand::rngs::SmallRng::from_entropy();
+    let alphabet = random_alphabet(&mut rng);
+    let custom = Config::new(CharacterSet::Custom(alphabet), true);
+
+    assert_eq!(STANDARD, Config::new(CharacterSet::Standard, true));
+    assert_ne!(STANDARD, STANDARD.pad(false));
+    assert_ne!(STANDARD, STANDARD.decode_allow_trailing_bits(true));
+    assert_ne!(STANDARD, Config::new(CharacterSet::UrlSafe, true));
+    // custom alphabets compare by their symbols
+    assert_eq!(
+        custom,
+        Config::new(
+            CharacterSet::Custom(CustomAlphabet::new(alphabet.encode_table()).unwrap()),
+            true
+        )
+    );
+    assert_ne!(custom, STANDARD);
+
+    let mut configs = HashSet::new();
+    assert!(configs.insert(STANDARD));
+    assert!(configs.insert(custom));
+    assert!(configs.insert(STANDARD.pad_byte(b'.')));
+    assert!(!configs.insert(Config::new(CharacterSet::Standard, true)));
+    assert!(!configs.insert(custom.pad(true)));
+}
+
 pub fn assert_encode_sanity(encoded: &str, config: Config, input_len: usize) {
     let input_rem = input_len % 3;
     let expected_padding_len = if input_rem > 0 {
@@ -31,11 +467,11 @@ pub fn assert_encode_sanity(encoded: &str, config: Config, input_len: usize) {
         0
     };
 
-    let expected_encoded_len = encoded_size(input_len, config).unwrap();
+    let expected_encoded_len = encoded_len(input_len, config).unwrap();
 
     assert_eq!(expected_encoded_len, encoded.len());
 
-    let padding_len = encoded.chars().filter(|&c| c == '=').count();
+    let padding_len = encoded.bytes().filter(|&b| b == config.pad_byte).count();
 
     assert_eq!(expected_padding_len, padding_len);
 
@@ -79,3 +515,95 @@ pub fn random_config<R: Rng>(rng: &mut R) -> Config {
 
     Config::new(charset, rng.gen())
 }
+
+/// Like `random_config`, but half the time with a random custom alphabet, and sometimes with a
+/// padding byte other than `=`.
+pub fn random_config_with_custom_alphabet<R: Rng>(rng: &mut R) -> Config {
+    let config = if rng.gen() {
+        Config::new(CharacterSet::Custom(random_alphabet(rng)), rng.gen())
+    } else {
+        random_config(rng)
+    };
+
+    if rng.gen() {
+        // any printable ASCII that isn't part of the alphabet will do
+        let pad_byte = loop {
+            let b = rng.gen_range(b'!', b'~' + 1);
+            if !config.char_set.encode_table().contains(&b) {
+                break b;
+            }
+        };
+
+        config.pad_byte(pad_byte)
+    } else {
+        config
+    }
+}
+
+/// Sets all of the `decode_*` options of `config`, other than `decode_max_len`, at random.
+pub fn random_decode_options<R: Rng>(config: Config, rng: &mut R) -> Config {
+    const PADDING_MODES: &[DecodePaddingMode] = &[
+        DecodePaddingMode::Indifferent,
+        DecodePaddingMode::RequireCanonical,
+        DecodePaddingMode::IgnoreTrailing,
+    ];
+
+    config
+        .decode_allow_trailing_bits(rng.gen())
+        .decode_ignore_invalid(rng.gen())
+        .decode_allow_trailing_whitespace(rng.gen())
+        .decode_constant_time(rng.gen())
+        .decode_padding_mode(*PADDING_MODES.choose(rng).unwrap())
+        .decode_url_escaped_padding(rng.gen())
+}
+
+/// Sometimes URL-escapes the padding at the end of `encoded`, and sometimes adds whitespace after
+/// it, so the options that allow for those get exercised.
+///
+/// Returns whether `encoded` is still valid input for `config`, which it isn't if any padding was
+/// escaped, or if whitespace was added that `config` neither allows nor ignores.
+pub fn escape_padding_and_add_whitespace<R: Rng>(
+    encoded: &mut Vec<u8>,
+    config: Config,
+    rng: &mut R,
+) -> bool {
+    let pad_byte = config.pad_byte;
+    let num_pads = encoded.iter().rev().take_while(|&&b| b == pad_byte).count();
+    // a `%` padding byte would be escaped as padding followed by symbols
+    let escape = num_pads > 0 && pad_byte != b'%' && rng.gen();
+    if escape {
+        let escaped = if rng.gen() {
+            format!("%{:02X}", pad_byte)
+        } else {
+            format!("%{:02x}", pad_byte)
+        };
+
+        encoded.truncate(encoded.len() - num_pads);
+        for _ in 0..num_pads {
+            encoded.extend_from_slice(escaped.as_bytes());
+        }
+    }
+
+    let add_whitespace = rng.gen();
+    if add_whitespace {
+        for _ in 0..rng.gen_range(1, 4) {
+            encoded.push(*b" \t\r\n".choose(rng).unwrap());
+        }
+    }
+
+    !escape
+        && (!add_whitespace
+            || config.decode_allow_trailing_whitespace
+            || config.decode_ignore_invalid)
+}
+
+pub fn random_alphabet<R: Rng>(rng: &mut R) -> CustomAlphabet {
+    // printable ASCII, minus the padding byte
+    let mut candidates: Vec<u8> = (b'!'..=b'~').filter(|&b| b != b'=').collect();
+    candidates.shuffle(rng);
+
+    let mut symbols = [0_u8; 64];
+    symbols.copy_from_slice(&candidates[..64]);
+
+    CustomAlphabet::new(&symbols).unwrap()
+}
@@ -0,0 +1,76 @@
+use crate::{decode_config_buf, encode_config, Config, DecodeError};
+use alloc::{string::String, vec::Vec};
+
+///Decode base64 with one config and encode the result with another, e.g. to pass along URL-safe
+///unpadded input as standard padded base64.
+///Returns a Result containing a String.
+///
+///Everything about reading the input comes from `from`: its character set, how padding is
+///checked, and whether invalid bytes or trailing whitespace are skipped. Everything about the
+///output comes from `to`, so `to`'s decoding settings make no difference here.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    assert_eq!(
+///        Ok("+/+/aGk=".to_string()),
+///        base64::transcode("-_-_aGk", base64::URL_SAFE_NO_PAD, base64::STANDARD)
+///    );
+///    assert_eq!(
+///        Err(base64::DecodeError::InvalidByte(0, b'+')),
+///        base64::transcode("+/+/aGk=", base64::URL_SAFE_NO_PAD, base64::STANDARD)
+///    );
+///}
+///```
+pub fn transcode<T: AsRef<[u8]>>(
+    input: T,
+    from: Config,
+    to: Config,
+) -> Result<String, DecodeError> {
+    let mut decoded = Vec::new();
+    decode_config_buf(input, from, &mut decoded)?;
+
+    Ok(encode_config(decoded, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_config, tests::random_config, STANDARD, URL_SAFE_NO_PAD};
+
+    use rand::{FromEntropy, Rng};
+
+    #[test]
+    fn transcode_matches_decode_then_encode() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let from = random_config(&mut rng);
+            let to = random_config(&mut rng);
+            let data: Vec<u8> = (0..rng.gen_range(0, 1_000)).map(|_| rng.gen()).collect();
+
+            let transcoded = transcode(encode_config(&data, from), from, to).unwrap();
+            assert_eq!(encode_config(&data, to), transcoded);
+            assert_eq!(data, decode_config(&transcoded, to).unwrap());
+        }
+    }
+
+    #[test]
+    fn transcode_reads_with_from_config() {
+        // the whitespace and stray byte are only allowed by the input's config
+        let from = URL_SAFE_NO_PAD
+            .decode_ignore_invalid(true)
+            .decode_allow_trailing_whitespace(true);
+        assert_eq!(
+            Ok("+/+/aGk=".to_string()),
+            transcode("-_-_\r\naGk \n", from, STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidByte(4, b'\r')),
+            transcode("-_-_\r\naGk \n", URL_SAFE_NO_PAD, STANDARD)
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{decode_config, Config, DecodeError};
+use alloc::vec::Vec;
+use core::{cmp, fmt};
+#[cfg(any(feature = "std", test))]
+use std::error;
+
+// how many bytes of input to include on either side of the offending byte
+const CONTEXT_RADIUS: usize = 10;
+
+///Decode from string reference as octets, reporting errors with the surrounding input.
+///Returns a Result containing a Vec<u8>.
+///
+///This is otherwise just like `decode_config`; use it where errors are going to be shown to a
+///person, as the extra context makes it easy to point at the offending byte.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let err = base64::decode_verbose("aGVsbG8*d29ybGQ=", base64::STANDARD).unwrap_err();
+///    assert_eq!(&base64::DecodeError::InvalidByte(7, b'*'), err.error());
+///    assert_eq!(
+///        "Invalid byte '*', offset 7.\n  aGVsbG8*d29ybGQ=\n         ^",
+///        err.to_string()
+///    );
+///}
+///```
+pub fn decode_verbose<T: AsRef<[u8]> + ?Sized>(
+    input: &T,
+    config: Config,
+) -> Result<Vec<u8>, VerboseDecodeError<'_>> {
+    let input = input.as_ref();
+
+    decode_config(input, config).map_err(|error| {
+        let index = match error {
+            DecodeError::InvalidByte(index, _) | DecodeError::InvalidLastSymbol(index, _) => index,
+            _ => {
+                return VerboseDecodeError {
+                    error,
+                    context: &[],
+                    context_start: 0,
+                }
+            }
+        };
+
+        let context_start = index.saturating_sub(CONTEXT_RADIUS);
+        let context_end = cmp::min(input.len(), index + CONTEXT_RADIUS + 1);
+
+        VerboseDecodeError {
+            error,
+            context: &input[context_start..context_end],
+            context_start,
+        }
+    })
+}
+
+/// A `DecodeError` along with the part of the input around where it occurred.
+///
+/// The `Display` impl shows the context below the error, with a caret under the offending byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerboseDecodeError<'a> {
+    error: DecodeError,
+    context: &'a [u8],
+    // offset of the context in the input
+    context_start: usize,
+}
+
+impl<'a> VerboseDecodeError<'a> {
+    /// The underlying error.
+    pub fn error(&self) -> &DecodeError {
+        &self.error
+    }
+
+    /// The input around the offending byte, or an empty slice for errors that don't refer to a
+    /// particular byte, like `DecodeError::InvalidLength`.
+    pub fn context(&self) -> &'a [u8] {
+        self.context
+    }
+
+    /// The offset of the offending byte within `context`, if there is one.
+    pub fn offset_in_context(&self) -> Option<usize> {
+        match self.error {
+            DecodeError::InvalidByte(index, _) | DecodeError::InvalidLastSymbol(index, _) => {
+                Some(index - self.context_start)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'a> fmt::Display for VerboseDecodeError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+
+        if let Some(offset) = self.offset_in_context() {
+            f.write_str("\n  ")?;
+            // one char per byte, so the caret lines up
+            for &b in self.context {
+                let c = if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '?'
+                };
+                write!(f, "{}", c)?;
+            }
+
+            write!(f, "\n  {:>width$}", '^', width = offset + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl<'a> error::Error for VerboseDecodeError<'a> {
+    fn description(&self) -> &str {
+        #[allow(deprecated)]
+        self.error.description()
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STANDARD;
+
+    #[test]
+    fn verbose_error_context_is_clamped_to_input() {
+        let err = decode_verbose("*AAA", STANDARD).unwrap_err();
+        assert_eq!(b"*AAA", err.context());
+        assert_eq!(Some(0), err.offset_in_context());
+        assert_eq!("Invalid byte '*', offset 0.\n  *AAA\n  ^", err.to_string());
+
+        let input = "AAAA".repeat(10) + "AA\nA";
+        let err = decode_verbose(&input, STANDARD).unwrap_err();
+        assert_eq!(&DecodeError::InvalidByte(42, b'\n'), err.error());
+        assert_eq!(&input.as_bytes()[32..], err.context());
+        assert_eq!(Some(10), err.offset_in_context());
+        assert_eq!(
+            "Invalid byte 0x0a, offset 42.\n  AAAAAAAAAA?A\n            ^",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn verbose_error_without_offset_has_no_context() {
+        let err = decode_verbose("AAAAA", STANDARD).unwrap_err();
+        assert_eq!(&DecodeError::InvalidLength, err.error());
+        assert!(err.context().is_empty());
+        assert_eq!(None, err.offset_in_context());
+        assert_eq!(
+            "Encoded text cannot have a 6-bit remainder.",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn verbose_error_points_at_last_symbol() {
+        let err = decode_verbose("YWJjZR==", STANDARD).unwrap_err();
+        assert_eq!(&DecodeError::InvalidLastSymbol(5, b'R'), err.error());
+        assert_eq!(
+            "Invalid last symbol 82, offset 5.\n  YWJjZR==\n       ^",
+            err.to_string()
+        );
+    }
+}
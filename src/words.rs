@@ -0,0 +1,167 @@
+use crate::{decode_config, encode_config_buf, Config, DecodeError};
+use alloc::{string::String, vec::Vec};
+
+// A multiple of 3, so only the last chunk gets padding, and of every word size, so no word
+// straddles two chunks
+const BUF_SIZE: usize = 768;
+
+/// An unsigned integer type that `encode_words` and `decode_words` can convert to and from bytes.
+///
+/// This is implemented for `u16`, `u32`, `u64`, and `u128`, and can't be implemented outside this
+/// crate.
+pub trait Word: Copy + private::Sealed {}
+
+mod private {
+    pub trait Sealed: Sized {
+        const SIZE: usize;
+
+        fn write_be(self, output: &mut [u8]);
+
+        fn read_be(input: &[u8]) -> Self;
+    }
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl private::Sealed for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            fn write_be(self, output: &mut [u8]) {
+                output.copy_from_slice(&self.to_be_bytes());
+            }
+
+            fn read_be(input: &[u8]) -> Self {
+                let mut bytes = [0_u8; core::mem::size_of::<$t>()];
+                bytes.copy_from_slice(input);
+                <$t>::from_be_bytes(bytes)
+            }
+        }
+
+        impl Word for $t {}
+    )*};
+}
+
+impl_word!(u16, u32, u64, u128);
+
+///Encode a slice of words as base64, each word as its big-endian bytes.
+///Returns a String.
+///
+///This is the same as converting each word with `to_be_bytes` and encoding the concatenation, but
+///without building the whole byte array first.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let b64 = base64::encode_words(&[0x0102_0304_u32, 0x0506_0708], base64::STANDARD);
+///    assert_eq!(base64::encode(&[1, 2, 3, 4, 5, 6, 7, 8]), b64);
+///}
+///```
+pub fn encode_words<W: Word>(input: &[W], config: Config) -> String {
+    let mut buf = [0_u8; BUF_SIZE];
+    let mut encoded = String::new();
+
+    for words in input.chunks(BUF_SIZE / W::SIZE) {
+        let bytes = &mut buf[..words.len() * W::SIZE];
+        for (&word, output) in words.iter().zip(bytes.chunks_mut(W::SIZE)) {
+            word.write_be(output);
+        }
+
+        encode_config_buf(bytes, config, &mut encoded);
+    }
+
+    encoded
+}
+
+///Decode base64 into a Vec of words, reading each word from big-endian bytes.
+///Returns a Result containing a Vec<W>.
+///
+///This is the inverse of `encode_words`. If the input doesn't decode to a whole number of words,
+///`DecodeError::UnexpectedLength` is returned.
+///
+///# Example
+///
+///```rust
+///extern crate base64;
+///
+///fn main() {
+///    let words: Vec<u16> = base64::decode_words("AQIDBA==", base64::STANDARD).unwrap();
+///    assert_eq!(vec![0x0102, 0x0304], words);
+///
+///    assert_eq!(
+///        Err(base64::DecodeError::UnexpectedLength),
+///        base64::decode_words::<_, u16>("AQID", base64::STANDARD).map(|_| ())
+///    );
+///}
+///```
+pub fn decode_words<T: AsRef<[u8]> + ?Sized, W: Word>(
+    input: &T,
+    config: Config,
+) -> Result<Vec<W>, DecodeError> {
+    let bytes = decode_config(input, config)?;
+
+    if bytes.len() % W::SIZE != 0 {
+        return Err(DecodeError::UnexpectedLength);
+    }
+
+    Ok(bytes.chunks(W::SIZE).map(W::read_be).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        decode_config, encode_config,
+        tests::{assert_encode_sanity, random_config},
+    };
+
+    use rand::{FromEntropy, Rng};
+
+    #[test]
+    fn words_roundtrip_random_config() {
+        let mut rng = rand::rngs::SmallRng::from_entropy();
+
+        for _ in 0..1_000 {
+            let config = random_config(&mut rng);
+            // long enough to span several chunks of u128s
+            let words: Vec<u32> = (0..rng.gen_range(0, 1_000)).map(|_| rng.gen()).collect();
+            let bytes: Vec<u8> = words
+                .iter()
+                .flat_map(|w| w.to_be_bytes().to_vec())
+                .collect();
+
+            let encoded = encode_words(&words, config);
+            assert_encode_sanity(&encoded, config, bytes.len());
+            assert_eq!(encode_config(&bytes, config), encoded);
+            assert_eq!(words, decode_words::<_, u32>(&encoded, config).unwrap());
+
+            let wide: Vec<u128> = words.iter().map(|&w| u128::from(w) << 64 | 1).collect();
+            let encoded = encode_words(&wide, config);
+            assert_eq!(wide, decode_words::<_, u128>(&encoded, config).unwrap());
+            assert_eq!(
+                wide.len() * 16,
+                decode_config(&encoded, config).unwrap().len()
+            );
+        }
+    }
+
+    #[test]
+    fn decode_words_rejects_partial_words() {
+        let encoded = encode_config(b"abcdefg", crate::STANDARD);
+
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_words::<_, u64>(&encoded, crate::STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::UnexpectedLength),
+            decode_words::<_, u16>(&encoded, crate::STANDARD)
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidByte(1, b'*')),
+            decode_words::<_, u16>("a*cd", crate::STANDARD)
+        );
+    }
+}
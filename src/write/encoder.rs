@@ -1,4 +1,4 @@
-use crate::encode::encode_to_slice;
+use crate::encode::encode_with_table;
 use crate::{encode_config_slice, Config};
 use std::{
     cmp, fmt,
@@ -312,7 +312,7 @@ impl<W: Write> Write for EncoderWriter<W> {
                 self.extra_input[self.extra_input_occupied_len..MIN_ENCODE_CHUNK_SIZE]
                     .copy_from_slice(&input[0..extra_input_read_len]);
 
-                let len = encode_to_slice(
+                let len = encode_with_table(
                     &self.extra_input[0..MIN_ENCODE_CHUNK_SIZE],
                     &mut self.output[..],
                     self.config.char_set.encode_table(),
@@ -361,7 +361,7 @@ impl<W: Write> Write for EncoderWriter<W> {
         debug_assert_eq!(0, max_input_len % MIN_ENCODE_CHUNK_SIZE);
         debug_assert_eq!(0, input_chunks_to_encode_len % MIN_ENCODE_CHUNK_SIZE);
 
-        encoded_size += encode_to_slice(
+        encoded_size += encode_with_table(
             &input[..(input_chunks_to_encode_len)],
             &mut self.output[encoded_size..],
             self.config.char_set.encode_table(),
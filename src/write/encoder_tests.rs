@@ -1,3 +1,5 @@
+#![allow(clippy::len_zero, clippy::unnecessary_literal_unwrap)]
+
 use super::EncoderWriter;
 use crate::tests::random_config;
 use crate::{encode_config, encode_config_buf, STANDARD_NO_PAD, URL_SAFE};
@@ -1,3 +1,9 @@
+#![allow(
+    clippy::manual_str_repeat,
+    clippy::single_char_add_str,
+    clippy::slow_vector_initialization
+)]
+
 extern crate base64;
 
 use base64::*;
@@ -104,14 +110,9 @@ fn decode_1_pad_byte_in_fast_loop_then_extra_padding_chunk_error() {
         let mut s: String = std::iter::repeat("ABCD").take(num_quads).collect();
         s.push_str("YWxpY2U=====");
 
-        // since the first 8 bytes are handled in stage 1 or 2, the padding is detected as a
-        // generic invalid byte, not specifcally a padding issue.
-        // Could argue that the *next* padding byte (in the next quad) is technically the first
-        // erroneous one, but reporting that accurately is more complex and probably nobody cares
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 7, b'='),
-            decode(&s).unwrap_err()
-        );
+        // since the first 8 bytes are handled in stage 1 or 2, the padding is detected there as
+        // an invalid byte, but since it's the padding byte it's still reported as bad padding
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -122,11 +123,7 @@ fn decode_2_pad_bytes_in_leftovers_then_extra_padding_chunk_error() {
         s.push_str("YWxpY2UABB====");
 
         // 6 bytes (4 padding) after last 8-byte chunk, so it's decoded by stage 4.
-        // First padding byte is invalid.
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 10, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -137,11 +134,7 @@ fn decode_valid_bytes_after_padding_in_leftovers_error() {
         s.push_str("YWxpY2UABB=B");
 
         // 4 bytes after last 8-byte chunk, so it's decoded by stage 4.
-        // First (and only) padding byte is invalid.
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 10, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -152,11 +145,7 @@ fn decode_absurd_pad_error() {
         s.push_str("==Y=Wx===pY=2U=====");
 
         // Plenty of remaining bytes, so handled by stage 1 or 2.
-        // first padding byte
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -167,11 +156,7 @@ fn decode_extra_padding_after_1_pad_bytes_in_trailing_quad_returns_error() {
         s.push_str("EEE===");
 
         // handled by stage 1, 2, or 4 depending on length
-        // first padding byte -- which would be legal if it was the only padding
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 3, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -182,11 +167,7 @@ fn decode_extra_padding_after_2_pad_bytes_in_trailing_quad_2_returns_error() {
         s.push_str("EE====");
 
         // handled by stage 1, 2, or 4 depending on length
-        // first padding byte -- which would be legal if it was by itself
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 2, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -206,10 +187,7 @@ fn decode_start_quad_with_padding_returns_error() {
                 // padding lengths 2 - 8 are handled by stage 4
                 // padding length >= 8 will hit at least one chunk at stages 1, 2, 3 at different
                 // prefix lengths
-                assert_eq!(
-                    DecodeError::InvalidByte(num_quads * 4, b'='),
-                    decode(&s).unwrap_err()
-                );
+                assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
             }
         }
     }
@@ -229,11 +207,7 @@ fn decode_padding_followed_by_non_padding_returns_error() {
             } else {
                 // pad len 1 - 8 will be handled by stage 4
                 // pad len 9 (suffix len 10) will have 8 bytes of padding handled by stage 3
-                // first padding byte
-                assert_eq!(
-                    DecodeError::InvalidByte(num_quads * 4, b'='),
-                    decode(&s).unwrap_err()
-                );
+                assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
             }
         }
     }
@@ -245,23 +219,14 @@ fn decode_one_char_in_quad_with_padding_error() {
         let mut s: String = std::iter::repeat("ABCD").take(num_quads).collect();
         s.push_str("E=");
 
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 1, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
 
         // more padding doesn't change the error
         s.push_str("=");
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 1, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
 
         s.push_str("=");
-        assert_eq!(
-            DecodeError::InvalidByte(num_quads * 4 + 1, b'='),
-            decode(&s).unwrap_err()
-        );
+        assert_eq!(DecodeError::InvalidPadding, decode(&s).unwrap_err());
     }
 }
 
@@ -275,6 +240,49 @@ fn decode_one_char_in_quad_without_padding_error() {
     }
 }
 
+#[test]
+fn decode_padding_without_enough_symbols_error() {
+    // a lone pad byte fails the early length check, like any single byte
+    let cases = [
+        ("=", DecodeError::InvalidLength),
+        ("==", DecodeError::InvalidPadding),
+        ("===", DecodeError::InvalidPadding),
+        ("====", DecodeError::InvalidPadding),
+        ("=AAA", DecodeError::InvalidPadding),
+        ("A===", DecodeError::InvalidPadding),
+    ];
+
+    for &(input, ref expected) in cases.iter() {
+        assert_eq!(Err(expected.clone()), decode(input), "{}", input);
+        assert_eq!(
+            Err(expected.clone()),
+            decode_config_slice(input, STANDARD, &mut [0; 8]),
+            "{}",
+            input
+        );
+        assert_eq!(
+            Err(expected.clone()),
+            decode_config(input, STANDARD.decode_constant_time(true)),
+            "{}",
+            input
+        );
+        assert_eq!(
+            Err(expected.clone()),
+            decode_in_place(&mut input.as_bytes().to_vec(), STANDARD),
+            "{}",
+            input
+        );
+
+        // decoding a byte at a time finds the padding before it knows the length
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_iter(input.bytes(), STANDARD),
+            "{}",
+            input
+        );
+    }
+}
+
 #[test]
 fn decode_reject_invalid_bytes_with_correct_error() {
     for length in 1..100 {
@@ -313,6 +321,24 @@ fn decode_imap() {
     );
 }
 
+#[test]
+fn imap_mailbox_name_roundtrip() {
+    // the shifted parts of "~peter/mail/&U,BTFw-/&ZeVnLIqe-" from RFC 3501 section 5.1.3, which
+    // are the UTF-16BE of U+53F0 U+5317 and U+65E5 U+672C U+8A9E
+    let names: [(&str, &[u8]); 2] = [
+        ("U,BTFw", &[0x53, 0xF0, 0x53, 0x17]),
+        ("ZeVnLIqe", &[0x65, 0xE5, 0x67, 0x2C, 0x8A, 0x9E]),
+    ];
+
+    for &(encoded, utf16) in &names {
+        assert_eq!(
+            utf16,
+            &decode_config(encoded, crate::IMAP_MUTF7).unwrap()[..]
+        );
+        assert_eq!(encoded, encode_config(utf16, crate::IMAP_MUTF7));
+    }
+}
+
 #[test]
 fn decode_invalid_trailing_bytes() {
     // The case of trailing newlines is common enough to warrant a test for a good error
@@ -325,6 +351,212 @@ fn decode_invalid_trailing_bytes() {
     assert_eq!(Err(DecodeError::InvalidLength), decode(b"Zm9vCg==="));
 }
 
+#[test]
+fn decode_rejects_nonzero_trailing_bits_by_default() {
+    // each of these decodes to the same bytes as the canonical encoding before it, if the bits
+    // that don't make up a whole byte are ignored
+    let cases = [
+        ("YQ==", "YR==", 1, b'R'),
+        ("YQ", "YR", 1, b'R'),
+        ("YWI=", "YWJ=", 2, b'J'),
+        ("YWJjYQ==", "YWJjYf==", 5, b'f'),
+    ];
+
+    for &(canonical, noncanonical, offset, byte) in cases.iter() {
+        let expected = Some(DecodeError::InvalidLastSymbol(offset, byte));
+        let lenient = STANDARD.decode_allow_trailing_bits(true);
+
+        assert_eq!(expected, decode(noncanonical).err());
+        assert_eq!(
+            expected,
+            decode_config(noncanonical, STANDARD.decode_constant_time(true)).err()
+        );
+        assert_eq!(
+            expected,
+            decode_config(noncanonical, STANDARD.decode_ignore_invalid(true)).err()
+        );
+        assert_eq!(
+            expected,
+            decode_config_slice(noncanonical, STANDARD, &mut [0; 8]).err()
+        );
+        assert_eq!(
+            expected,
+            decode_in_place(&mut noncanonical.as_bytes().to_vec(), STANDARD).err()
+        );
+        assert_eq!(expected, decode_iter(noncanonical.bytes(), STANDARD).err());
+
+        let mut stream = StreamDecoder::new(STANDARD);
+        assert_eq!(
+            expected,
+            stream
+                .feed(noncanonical.as_bytes())
+                .and(stream.finish())
+                .err()
+        );
+
+        assert_eq!(decode(canonical), decode_config(noncanonical, lenient));
+    }
+}
+
+#[test]
+fn decode_require_canonical_padding() {
+    let config = STANDARD.decode_padding_mode(DecodePaddingMode::RequireCanonical);
+
+    for num_quads in 0..25 {
+        let prefix: String = "ABCD".repeat(num_quads);
+
+        for (suffix, expected) in &[
+            ("", Ok(0)),
+            ("Zg==", Ok(1)),
+            ("Zm8=", Ok(2)),
+            ("Zm9v", Ok(3)),
+            ("Zg", Err(DecodeError::InvalidPadding)),
+            ("Zg=", Err(DecodeError::InvalidPadding)),
+            ("Zm8", Err(DecodeError::InvalidPadding)),
+        ] {
+            let input = prefix.clone() + suffix;
+            let expected = expected.clone().map(|len| num_quads * 3 + len);
+            assert_eq!(expected, decode_config(&input, config).map(|v| v.len()));
+            assert_eq!(
+                expected,
+                decode_config(&input, config.decode_ignore_invalid(true)).map(|v| v.len())
+            );
+        }
+    }
+}
+
+#[test]
+fn decode_ignore_trailing_padding() {
+    let config = STANDARD.decode_padding_mode(DecodePaddingMode::IgnoreTrailing);
+
+    for num_quads in 0..25 {
+        let prefix: String = "ABCD".repeat(num_quads);
+        let mut expected = decode(&prefix).unwrap();
+        expected.push(b'f');
+
+        for pad_bytes in 0..20 {
+            let input = prefix.clone() + "Zg" + &"=".repeat(pad_bytes);
+            assert_eq!(Ok(&expected), decode_config(&input, config).as_ref());
+            assert_eq!(
+                Ok(&expected),
+                decode_config(&input, config.decode_ignore_invalid(true)).as_ref()
+            );
+
+            let (_, metadata) = decode_config_with_info(&input, config).unwrap();
+            assert_eq!(pad_bytes, metadata.padding_len());
+        }
+
+        // padding that isn't trailing is still an error
+        assert_eq!(
+            Err(DecodeError::InvalidPadding),
+            decode_config(prefix.clone() + "Zg==Zg==", config)
+        );
+        // as are the usual invalid lengths
+        assert_eq!(
+            Err(DecodeError::InvalidLength),
+            decode_config(prefix.clone() + "ZmZmZ===", config)
+        );
+    }
+}
+
+#[test]
+fn decode_max_len_allows_exactly_max_len() {
+    for len in 0..50 {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+        for &config in &[
+            STANDARD,
+            STANDARD_NO_PAD,
+            STANDARD.decode_ignore_invalid(true),
+        ] {
+            let encoded = encode_config(&data, config);
+            let mut slice = [0_u8; 100];
+
+            let limited = config.decode_max_len(Some(len));
+            assert_eq!(Ok(&data), decode_config(&encoded, limited).as_ref());
+            assert_eq!(
+                Ok(len),
+                decode_config_slice(&encoded, limited, &mut slice[..])
+            );
+
+            if len > 0 {
+                let too_limited = config.decode_max_len(Some(len - 1));
+                assert_eq!(
+                    Err(DecodeError::TooLong),
+                    decode_config(&encoded, too_limited)
+                );
+                assert_eq!(
+                    Err(DecodeError::TooLong),
+                    decode_config_slice(&encoded, too_limited, &mut slice[..])
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_max_len_limits_allocation() {
+    let encoded = "AAAA".repeat(1_000_000);
+    let mut buf = vec![1, 2, 3];
+
+    assert_eq!(
+        Err(DecodeError::TooLong),
+        decode_config_buf(&encoded, STANDARD.decode_max_len(Some(10)), &mut buf)
+    );
+    assert!(buf.capacity() < 100);
+}
+
+#[test]
+fn decode_max_len_doesnt_hide_slice_too_small() {
+    let mut slice = [0_u8; 2];
+
+    assert_eq!(
+        Err(DecodeError::OutputSliceTooSmall),
+        decode_config_slice("YWJj", STANDARD.decode_max_len(Some(10)), &mut slice[..])
+    );
+}
+
+#[test]
+fn decode_url_safe_no_pad_with_trailing_newline() {
+    // the header, payload, and signature of a JWT, which are URL-safe and unpadded, with lengths of
+    // 0, 2, and 3 mod 4
+    let segments = [
+        "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9",
+        "eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ",
+        "SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c",
+    ];
+    let config = URL_SAFE_NO_PAD.decode_allow_trailing_whitespace(true);
+
+    assert_eq!(
+        Ok(b"{\"alg\":\"HS256\",\"typ\":\"JWT\"}".to_vec()),
+        decode_config(segments[0], URL_SAFE_NO_PAD)
+    );
+
+    for &segment in segments.iter() {
+        let expected = decode_config(segment, URL_SAFE_NO_PAD).unwrap();
+
+        // some of these would make the length invalid if they were counted
+        for &ending in &["\n", "\r\n", "\n\n\n", " \t\n"] {
+            let input = segment.to_string() + ending;
+
+            assert!(decode_config(&input, URL_SAFE_NO_PAD).is_err());
+            assert_eq!(Ok(&expected), decode_config(&input, config).as_ref());
+            assert_eq!(Ok(&expected), decode_iter(input.bytes(), config).as_ref());
+
+            let mut slice = [0_u8; 100];
+            assert_eq!(
+                Ok(expected.len()),
+                decode_config_slice(&input, config, &mut slice[..])
+            );
+            assert_eq!(&expected[..], &slice[..expected.len()]);
+
+            let mut buf = input.clone().into_bytes();
+            assert_eq!(Ok(expected.len()), decode_in_place(&mut buf, config));
+            assert_eq!(&expected[..], &buf[..expected.len()]);
+        }
+    }
+}
+
 fn config_std_pad() -> Config {
     Config::new(CharacterSet::Standard, true)
 }
@@ -0,0 +1,97 @@
+extern crate base64;
+extern crate quickcheck;
+extern crate rand;
+
+use base64::*;
+use quickcheck::{quickcheck, Arbitrary, Gen};
+use rand::Rng;
+
+const PRESETS: &[Config] = &[
+    STANDARD,
+    STANDARD_NO_PAD,
+    URL_SAFE,
+    URL_SAFE_NO_PAD,
+    URL_SAFE_DOT_PAD,
+    CRYPT,
+    BCRYPT,
+    IMAP_MUTF7,
+    BINHEX,
+];
+
+// A preset with its other settings shuffled, so that their interactions get covered too
+#[derive(Clone, Copy, Debug)]
+struct ArbitraryConfig(Config);
+
+impl Arbitrary for ArbitraryConfig {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let preset = PRESETS[g.gen_range(0, PRESETS.len())];
+
+        ArbitraryConfig(
+            preset
+                .pad(g.gen())
+                .decode_allow_trailing_bits(g.gen())
+                .decode_constant_time(g.gen()),
+        )
+    }
+}
+
+fn roundtrips(input: &[u8], config: Config) -> bool {
+    let encoded = encode_config(input, config);
+
+    let mut slice_buf = vec![0; max_decoded_len(encoded.len())];
+    let slice_len = decode_config_slice(&encoded, config, &mut slice_buf).unwrap();
+
+    encoded.len() == encoded_len(input.len(), config).unwrap()
+        && decode_config(&encoded, config).unwrap() == input
+        && &slice_buf[..slice_len] == input
+}
+
+#[test]
+fn roundtrip_every_preset() {
+    fn prop(input: Vec<u8>) -> bool {
+        PRESETS.iter().all(|&config| roundtrips(&input, config))
+    }
+
+    quickcheck(prop as fn(Vec<u8>) -> bool);
+}
+
+#[test]
+fn roundtrip_arbitrary_config() {
+    fn prop(input: Vec<u8>, config: ArbitraryConfig) -> bool {
+        roundtrips(&input, config.0)
+    }
+
+    quickcheck(prop as fn(Vec<u8>, ArbitraryConfig) -> bool);
+}
+
+#[test]
+fn encodings_concatenate_after_whole_groups() {
+    // input that's a multiple of 3 bytes encodes without padding or leftover bits, so anything
+    // appended to its encoding decodes as if it had been appended to the input
+    fn prop(a: Vec<u8>, b: Vec<u8>, config: ArbitraryConfig) -> bool {
+        let config = config.0;
+        let a = &a[..a.len() - a.len() % 3];
+        let mut joined = encode_config(a, config);
+        encode_config_buf(&b, config, &mut joined);
+
+        decode_config(&joined, config).unwrap() == [a, &b[..]].concat()
+    }
+
+    quickcheck(prop as fn(Vec<u8>, Vec<u8>, ArbitraryConfig) -> bool);
+}
+
+#[test]
+fn decode_error_offsets_point_at_input() {
+    // errors that name a byte must name the byte at their offset, even when bytes are skipped
+    fn prop(input: Vec<u8>, config: ArbitraryConfig, ignore_invalid: bool) -> bool {
+        let config = config.0.decode_ignore_invalid(ignore_invalid);
+
+        match decode_config(&input, config) {
+            Err(DecodeError::InvalidByte(index, byte))
+            | Err(DecodeError::InvalidLastSymbol(index, byte)) => input.get(index) == Some(&byte),
+            _ => true,
+        }
+    }
+
+    quickcheck(prop as fn(Vec<u8>, ArbitraryConfig, bool) -> bool);
+}
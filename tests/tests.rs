@@ -1,3 +1,5 @@
+#![allow(clippy::manual_str_repeat, clippy::needless_borrows_for_generic_args)]
+
 extern crate base64;
 extern crate rand;
 